@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+
+use crate::{decode_payload, parse_payload, ParseError, ParsePsbtRequest, PsbtSummary};
+
+/// A bounded, thread-safe cache of summaries, evicting the least recently
+/// used one once full.
+///
+/// Entries are keyed by the decoded PSBT or raw transaction along with the
+/// request's options, network included, so differently encoded copies of a
+/// PSBT share an entry. Only successful summaries are cached.
+pub struct SummaryCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+}
+
+#[derive(Default)]
+struct Entries {
+    summaries: HashMap<sha256::Hash, (PsbtSummary, u64)>,
+    clock: u64,
+}
+
+impl SummaryCache {
+    /// Creates a cache holding up to `capacity` summaries.
+    pub fn new(capacity: usize) -> Self {
+        SummaryCache {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    /// How many summaries were served from the cache so far.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Summarizes `request` like `parse_request`, reusing the summary of an
+    /// identical earlier request.
+    pub fn parse(&self, request: &ParsePsbtRequest) -> Result<PsbtSummary, ParseError> {
+        let payload = decode_payload(request)?;
+        let key = cache_key(request, &payload);
+
+        if let Some(summary) = self.lookup(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(summary);
+        }
+        let summary = parse_payload(request, &payload, &request.options)?;
+        self.insert(key, summary.clone());
+        Ok(summary)
+    }
+
+    fn lookup(&self, key: &sha256::Hash) -> Option<PsbtSummary> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.clock += 1;
+        let clock = entries.clock;
+        let (summary, last_used) = entries.summaries.get_mut(key)?;
+        *last_used = clock;
+        Some(summary.clone())
+    }
+
+    fn insert(&self, key: sha256::Hash, summary: PsbtSummary) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        // Scanning for the oldest entry is cheap at the sizes a cache like
+        // this is configured with
+        if entries.summaries.len() >= self.capacity && !entries.summaries.contains_key(&key) {
+            let oldest = entries
+                .summaries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                entries.summaries.remove(&oldest);
+            }
+        }
+        entries.clock += 1;
+        let clock = entries.clock;
+        entries.summaries.insert(key, (summary, clock));
+    }
+}
+
+/// Hashes everything a summary of `request` depends on. The options are
+/// hashed in their debug form, which covers the fields set from the
+/// environment too and only has to stay stable within the process.
+fn cache_key(request: &ParsePsbtRequest, payload: &[u8]) -> sha256::Hash {
+    let settings = format!(
+        "{:?} {:?} {:?}",
+        request.payload_type, request.prevouts, request.options
+    );
+
+    let mut engine = sha256::Hash::engine();
+    engine.input(&(payload.len() as u64).to_le_bytes());
+    engine.input(payload);
+    engine.input(settings.as_bytes());
+    sha256::Hash::from_engine(engine)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{base64, p2wpkh, psbt, txout};
+
+    #[test]
+    fn serves_a_repeated_request_from_the_cache() {
+        let request = ParsePsbtRequest {
+            psbt: base64(&psbt(
+                &[txout(10_000, p2wpkh(1))],
+                &[txout(9_000, p2wpkh(2))],
+            )),
+            ..Default::default()
+        };
+        let cache = SummaryCache::new(4);
+
+        let first = cache.parse(&request).unwrap();
+        assert_eq!(cache.hits(), 0);
+        let second = cache.parse(&request).unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(first.summary_hash, second.summary_hash);
+    }
+}
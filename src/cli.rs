@@ -0,0 +1,56 @@
+use std::io::{Read, Write};
+use std::process::ExitCode;
+
+use psbt_parser::{parse_network, parse_psbt};
+
+const USAGE: &str = "usage: psbt_parser_cli [--file PATH] [--network NETWORK]
+
+Prints the JSON summary of a base64 PSBT read from PATH, or from stdin.";
+
+/// Reads the base64 PSBT named by the arguments and summarizes it.
+fn run() -> Result<String, String> {
+    let mut file = None;
+    let mut network = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--file" => file = Some(args.next().ok_or("--file needs a path")?),
+            "--network" => {
+                let name = args.next().ok_or("--network needs a network name")?;
+                network = Some(parse_network(&name).map_err(|e| e.to_string())?);
+            }
+            "-h" | "--help" => return Ok(USAGE.to_owned()),
+            _ => return Err(format!("unexpected argument `{}`\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let psbt = match file {
+        Some(path) => {
+            std::fs::read_to_string(&path).map_err(|e| format!("failed to read {}: {}", path, e))?
+        }
+        None => {
+            let mut psbt = String::new();
+            std::io::stdin()
+                .read_to_string(&mut psbt)
+                .map_err(|e| format!("failed to read stdin: {}", e))?;
+            psbt
+        }
+    };
+
+    let summary = parse_psbt(&psbt, network).map_err(|e| format!("{}: {}", e.code(), e))?;
+    serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        // A closed pipe, as when piping into `head`, isn't worth a panic
+        Ok(output) => match writeln!(std::io::stdout(), "{}", output) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(_) => ExitCode::FAILURE,
+        },
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use bitcoin::consensus::encode::serialize;
+
+use crate::{decode_base64, decode_psbt, ParseError};
+
+/// Merges the signatures and other data of several base64 PSBTs for the same
+/// transaction, such as the copies returned by each cosigner, into one base64
+/// PSBT.
+pub fn combine_psbts(psbts: &[String]) -> Result<String, ParseError> {
+    let mut decoded = psbts
+        .iter()
+        .map(|psbt| decode_psbt(&decode_base64(psbt)?, None));
+    let mut combined = match decoded.next() {
+        Some(psbt) => psbt?,
+        None => return Err(ParseError::NoPsbts),
+    };
+
+    for (index, psbt) in decoded.enumerate() {
+        let psbt = psbt?;
+        // Offset past the first PSBT, which the others are merged into
+        let index = index + 1;
+        if psbt.global.unsigned_tx != combined.global.unsigned_tx {
+            return Err(ParseError::IncompatiblePsbts {
+                index,
+                reason: String::from("its unsigned transaction differs from the first PSBT's"),
+            });
+        }
+        combined
+            .merge(psbt)
+            .map_err(|e| ParseError::IncompatiblePsbts {
+                index,
+                reason: e.to_string(),
+            })?;
+    }
+
+    Ok(base64::encode(serialize(&combined)))
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::consensus::encode::deserialize;
+    use bitcoin::util::psbt::PartiallySignedTransaction;
+
+    use super::combine_psbts;
+    use crate::test_util::{base64, multisig, p2wpkh, psbt, pubkey, txout, DUMMY_SIG};
+
+    #[test]
+    fn merges_each_cosigners_signature() {
+        let witness_script = multisig(2, 2);
+        let mut unsigned = psbt(
+            &[txout(10_000, witness_script.to_v0_p2wsh())],
+            &[txout(9_000, p2wpkh(3))],
+        );
+        unsigned.inputs[0].witness_script = Some(witness_script);
+        let (mut first, mut second) = (unsigned.clone(), unsigned);
+        first.inputs[0]
+            .partial_sigs
+            .insert(pubkey(1), DUMMY_SIG.to_vec());
+        second.inputs[0]
+            .partial_sigs
+            .insert(pubkey(2), DUMMY_SIG.to_vec());
+
+        let combined = combine_psbts(&[base64(&first), base64(&second)]).unwrap();
+        let combined: PartiallySignedTransaction =
+            deserialize(&base64::decode(combined).unwrap()).unwrap();
+        let signatures = &combined.inputs[0].partial_sigs;
+        assert_eq!(signatures.len(), 2);
+        assert!(signatures.contains_key(&pubkey(1)) && signatures.contains_key(&pubkey(2)));
+    }
+
+    #[test]
+    fn refuses_psbts_for_different_transactions() {
+        let first = psbt(&[txout(10_000, p2wpkh(1))], &[txout(9_000, p2wpkh(2))]);
+        let second = psbt(&[txout(10_000, p2wpkh(1))], &[txout(8_000, p2wpkh(2))]);
+
+        let error = combine_psbts(&[base64(&first), base64(&second)]).unwrap_err();
+        assert_eq!(error.code(), "INCOMPATIBLE_PSBTS");
+        assert!(error.to_string().starts_with("PSBT 1 can't be combined"));
+    }
+}
@@ -0,0 +1,143 @@
+use bitcoin::blockdata::script::Script;
+use bitcoin::hashes::hex::ToHex;
+use bitcoin::Network;
+
+use crate::{
+    decode_base64, decode_psbt, resolve_utxo, script_to_address, DiffResult, ParseError, Warning,
+};
+
+/// Names the destination of `script`, its address when it has one.
+fn destination(script: &Script, network: Network) -> String {
+    script_to_address(script, network)
+        .map(|address| address.to_string())
+        .unwrap_or_else(|| script.as_bytes().to_hex())
+}
+
+/// Compares the base64 PSBT `a` handed to a signer with the PSBT `b` it
+/// returned.
+///
+/// Any change to the outputs, the inputs or their amounts is reported as
+/// `TAMPERED`; a signer is only expected to add signatures.
+pub fn diff_psbts(a: &str, b: &str, network: Network) -> Result<DiffResult, ParseError> {
+    let a = decode_psbt(&decode_base64(a)?, None)?;
+    let b = decode_psbt(&decode_base64(b)?, None)?;
+    let (tx_a, tx_b) = (&a.global.unsigned_tx, &b.global.unsigned_tx);
+
+    let mut changes = Vec::new();
+
+    if tx_a.output.len() != tx_b.output.len() {
+        changes.push(format!(
+            "output count changed from {} to {}",
+            tx_a.output.len(),
+            tx_b.output.len()
+        ));
+    }
+    for (index, (before, after)) in tx_a.output.iter().zip(&tx_b.output).enumerate() {
+        if before.script_pubkey != after.script_pubkey {
+            changes.push(format!(
+                "output {} now pays {} instead of {}",
+                index,
+                destination(&after.script_pubkey, network),
+                destination(&before.script_pubkey, network)
+            ));
+        }
+        if before.value != after.value {
+            changes.push(format!(
+                "output {} amount changed from {} sat to {} sat",
+                index, before.value, after.value
+            ));
+        }
+    }
+
+    if tx_a.input.len() != tx_b.input.len() {
+        changes.push(format!(
+            "input count changed from {} to {}",
+            tx_a.input.len(),
+            tx_b.input.len()
+        ));
+    }
+    for (index, (before, after)) in tx_a.input.iter().zip(&tx_b.input).enumerate() {
+        if before.previous_output != after.previous_output {
+            changes.push(format!(
+                "input {} now spends {} instead of {}",
+                index, after.previous_output, before.previous_output
+            ));
+            continue;
+        }
+        // A signer may drop UTXO data, but mustn't change the amount spent
+        let amount_before = resolve_utxo(&a.inputs[index], &before.previous_output);
+        let amount_after = resolve_utxo(&b.inputs[index], &after.previous_output);
+        if let (Some(before), Some(after)) = (amount_before, amount_after) {
+            if before.value != after.value {
+                changes.push(format!(
+                    "input {} amount changed from {} sat to {} sat",
+                    index, before.value, after.value
+                ));
+            }
+        }
+    }
+
+    let unsigned_tx_identical = tx_a == tx_b;
+    if !unsigned_tx_identical && changes.is_empty() {
+        changes.push(String::from("the unsigned transaction changed"));
+    }
+
+    let signed_inputs = a
+        .inputs
+        .iter()
+        .zip(&b.inputs)
+        .enumerate()
+        .filter(|(_, (before, after))| {
+            after
+                .partial_sigs
+                .keys()
+                .any(|key| !before.partial_sigs.contains_key(key))
+        })
+        .map(|(index, _)| index)
+        .collect();
+
+    Ok(DiffResult {
+        unsigned_tx_identical,
+        signed_inputs,
+        tampered: changes
+            .into_iter()
+            .map(|change| Warning::new("TAMPERED", change))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Network;
+
+    use super::diff_psbts;
+    use crate::test_util::{base64, p2wpkh, psbt, sign, txout};
+
+    #[test]
+    fn signing_is_not_tampering() {
+        let unsigned = psbt(&[txout(10_000, p2wpkh(1))], &[txout(9_000, p2wpkh(2))]);
+        let mut signed = unsigned.clone();
+        sign(&mut signed, 0, 1);
+
+        let diff = diff_psbts(&base64(&unsigned), &base64(&signed), Network::Bitcoin).unwrap();
+        assert!(diff.unsigned_tx_identical);
+        assert_eq!(diff.signed_inputs, [0]);
+        assert!(diff.tampered.is_empty());
+    }
+
+    #[test]
+    fn reports_a_redirected_output() {
+        let unsigned = psbt(&[txout(10_000, p2wpkh(1))], &[txout(9_000, p2wpkh(2))]);
+        let mut tampered = unsigned.clone();
+        tampered.global.unsigned_tx.output[0].script_pubkey = p2wpkh(3);
+
+        let diff = diff_psbts(&base64(&unsigned), &base64(&tampered), Network::Bitcoin).unwrap();
+        assert!(!diff.unsigned_tx_identical);
+        assert!(diff.signed_inputs.is_empty());
+        assert_eq!(diff.tampered.len(), 1);
+        assert_eq!(diff.tampered[0].code, "TAMPERED");
+        assert!(diff.tampered[0]
+            .message
+            .starts_with("output 0 now pays bc1q"));
+    }
+}
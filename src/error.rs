@@ -0,0 +1,235 @@
+use bitcoin::blockdata::transaction::OutPoint;
+use bitcoin::consensus::encode;
+use bitcoin::hashes::hex;
+use bitcoin::{Amount, Network};
+use std::fmt;
+
+/// The ways summarizing a PSBT can fail.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The PSBT is not valid base64.
+    Base64(base64::DecodeError),
+    /// The PSBT is not valid hex.
+    Hex(hex::Error),
+    /// The decoded PSBT exceeds the configured size limit.
+    PsbtTooLarge { size: usize, max: usize },
+    /// The PSBT declares a version other than 0, which can't be parsed yet.
+    UnsupportedPsbtVersion { version: u32 },
+    /// The decoded bytes are not a valid PSBT.
+    Deserialize(encode::Error),
+    /// The decoded bytes are a raw transaction rather than a PSBT.
+    LooksLikeRawTx,
+    /// The decoded bytes are not a valid raw transaction.
+    InvalidRawTx(encode::Error),
+    /// The prevouts given for a raw transaction don't pair up with its
+    /// inputs.
+    PrevoutCountMismatch { inputs: usize, prevouts: usize },
+    /// A prevout given for a raw transaction has a script that isn't valid
+    /// hex.
+    InvalidPrevoutScript { index: usize, error: hex::Error },
+    /// The input amounts given don't pair up with the inputs.
+    InputAmountCountMismatch { inputs: usize, amounts: usize },
+    /// The PSBT resolves to a network outside the allowed ones.
+    NetworkNotAllowed { network: Network },
+    /// The unsigned transaction spends nothing.
+    NoInputs,
+    /// The unsigned transaction pays nothing.
+    NoOutputs,
+    /// Two inputs spend the same outpoint.
+    DuplicateInput {
+        first: usize,
+        second: usize,
+        outpoint: OutPoint,
+    },
+    /// An input's UTXO data doesn't describe the output it spends.
+    UtxoMismatch { input: usize, reason: &'static str },
+    /// An input spends an output its non-witness UTXO doesn't have.
+    VoutOutOfRange {
+        input: usize,
+        vout: u32,
+        outputs: usize,
+    },
+    /// The inputs or outputs add up to more than the 21 million BTC supply.
+    AmountOverflow { side: &'static str },
+    /// The inputs are worth less than the outputs.
+    FeeUnderflow {
+        input_amount: Amount,
+        output_amount: Amount,
+    },
+    /// A requested input index is past the transaction's inputs.
+    InputOutOfRange { input: usize, inputs: usize },
+    /// No PSBTs were given to combine.
+    NoPsbts,
+    /// A PSBT to combine doesn't fit with the first one.
+    IncompatiblePsbts { index: usize, reason: String },
+    /// The unsigned transaction is structurally unusable.
+    InvalidTransaction(&'static str),
+    /// Parsing failed for reasons unrelated to the PSBT, such as a panicking
+    /// worker thread.
+    Internal(String),
+}
+
+impl ParseError {
+    /// A stable, machine readable identifier for the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::Base64(_) => "INVALID_BASE64",
+            ParseError::Hex(_) => "INVALID_ENCODING",
+            ParseError::PsbtTooLarge { .. } => "PSBT_TOO_LARGE",
+            ParseError::UnsupportedPsbtVersion { .. } => "UNSUPPORTED_PSBT_VERSION",
+            ParseError::Deserialize(_) => "INVALID_PSBT",
+            ParseError::LooksLikeRawTx => "NOT_A_PSBT_LOOKS_LIKE_RAW_TX",
+            ParseError::InvalidRawTx(_) => "INVALID_RAW_TX",
+            ParseError::PrevoutCountMismatch { .. } => "PREVOUT_COUNT_MISMATCH",
+            ParseError::InvalidPrevoutScript { .. } => "INVALID_PREVOUT_SCRIPT",
+            ParseError::InputAmountCountMismatch { .. } => "INPUT_AMOUNT_COUNT_MISMATCH",
+            ParseError::NetworkNotAllowed { .. } => "NETWORK_NOT_ALLOWED",
+            ParseError::NoInputs => "NO_INPUTS",
+            ParseError::NoOutputs => "NO_OUTPUTS",
+            ParseError::DuplicateInput { .. } => "DUPLICATE_INPUT",
+            ParseError::UtxoMismatch { .. } => "UTXO_MISMATCH",
+            ParseError::VoutOutOfRange { .. } => "VOUT_OUT_OF_RANGE",
+            ParseError::AmountOverflow { .. } => "AMOUNT_OVERFLOW",
+            ParseError::FeeUnderflow { .. } => "FEE_UNDERFLOW",
+            ParseError::InputOutOfRange { .. } => "INPUT_OUT_OF_RANGE",
+            ParseError::NoPsbts => "NO_PSBTS",
+            ParseError::IncompatiblePsbts { .. } => "INCOMPATIBLE_PSBTS",
+            ParseError::InvalidTransaction(_) => "INVALID_TRANSACTION",
+            ParseError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Base64(e) => write!(f, "PSBT is not valid base64: {}", e),
+            ParseError::Hex(e) => write!(f, "PSBT is not valid hex: {}", e),
+            ParseError::PsbtTooLarge { size, max } => {
+                write!(
+                    f,
+                    "PSBT is {} bytes, more than the {} byte limit",
+                    size, max
+                )
+            }
+            ParseError::UnsupportedPsbtVersion { version } => {
+                write!(
+                    f,
+                    "PSBT version {} is not supported, only version 0 is",
+                    version
+                )
+            }
+            ParseError::Deserialize(e) => write!(f, "invalid PSBT: {}", e),
+            ParseError::LooksLikeRawTx => write!(
+                f,
+                "not a PSBT but a raw transaction, submit it as hex with type `rawtx` instead"
+            ),
+            ParseError::InvalidRawTx(e) => write!(f, "invalid raw transaction: {}", e),
+            ParseError::PrevoutCountMismatch { inputs, prevouts } => write!(
+                f,
+                "transaction has {} inputs but {} prevouts were given",
+                inputs, prevouts
+            ),
+            ParseError::InvalidPrevoutScript { index, error } => write!(
+                f,
+                "prevout {} has a script_pubkey that is not valid hex: {}",
+                index, error
+            ),
+            ParseError::InputAmountCountMismatch { inputs, amounts } => write!(
+                f,
+                "transaction has {} inputs but {} input amounts were given",
+                inputs, amounts
+            ),
+            ParseError::NetworkNotAllowed { network } => {
+                write!(f, "network {} is not allowed", network)
+            }
+            ParseError::NoInputs => write!(f, "transaction has no inputs"),
+            ParseError::NoOutputs => write!(f, "transaction has no outputs"),
+            ParseError::DuplicateInput {
+                first,
+                second,
+                outpoint,
+            } => write!(f, "inputs {} and {} both spend {}", first, second, outpoint),
+            ParseError::UtxoMismatch { input, reason } => write!(f, "input {}: {}", input, reason),
+            ParseError::VoutOutOfRange {
+                input,
+                vout,
+                outputs,
+            } => write!(
+                f,
+                "input {} spends output {} of a transaction with {} outputs",
+                input, vout, outputs
+            ),
+            ParseError::AmountOverflow { side } => {
+                write!(f, "{} total more than 21,000,000 BTC", side)
+            }
+            ParseError::FeeUnderflow {
+                input_amount,
+                output_amount,
+            } => write!(
+                f,
+                "inputs total {} sat but outputs total {} sat",
+                input_amount.as_sat(),
+                output_amount.as_sat()
+            ),
+            ParseError::InputOutOfRange { input, inputs } => write!(
+                f,
+                "input {} doesn't exist, transaction has {} inputs",
+                input, inputs
+            ),
+            ParseError::NoPsbts => write!(f, "no PSBTs to combine"),
+            ParseError::IncompatiblePsbts { index, reason } => {
+                write!(f, "PSBT {} can't be combined: {}", index, reason)
+            }
+            ParseError::InvalidTransaction(reason) => write!(f, "invalid transaction: {}", reason),
+            ParseError::Internal(reason) => write!(f, "internal error: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Base64(e) => Some(e),
+            ParseError::Hex(e) => Some(e),
+            ParseError::Deserialize(e) => Some(e),
+            ParseError::InvalidRawTx(e) => Some(e),
+            ParseError::InvalidPrevoutScript { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<base64::DecodeError> for ParseError {
+    fn from(e: base64::DecodeError) -> Self {
+        ParseError::Base64(e)
+    }
+}
+
+impl From<hex::Error> for ParseError {
+    fn from(e: hex::Error) -> Self {
+        ParseError::Hex(e)
+    }
+}
+
+impl From<encode::Error> for ParseError {
+    fn from(e: encode::Error) -> Self {
+        ParseError::Deserialize(e)
+    }
+}
+
+/// A network name `parse_network` doesn't recognize.
+#[derive(Debug, Clone)]
+pub struct UnknownNetwork(pub String);
+
+impl fmt::Display for UnknownNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown network `{}`, expected one of bitcoin, mainnet, testnet, signet or regtest",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownNetwork {}
@@ -0,0 +1,77 @@
+use bitcoin::Amount;
+
+use crate::{btc_trimmed, PsbtSummary};
+
+/// Describes what signing the summarized transaction does in one sentence,
+/// for reviewers who don't read the structured fields.
+pub(crate) fn explain(summary: &PsbtSummary) -> String {
+    let destination = summary
+        .send_address
+        .as_deref()
+        .unwrap_or("a script with no address");
+    let mut text = if summary.tx_type == "consolidation" {
+        format!(
+            "Consolidating {} BTC into {}",
+            btc_trimmed(summary.total_amount),
+            destination
+        )
+    } else {
+        format!(
+            "Sending {} BTC to {}",
+            btc_trimmed(summary.total_amount),
+            destination
+        )
+    };
+
+    match (summary.fee, summary.fee_rate_sat_vb) {
+        (Some(fee), Some(fee_rate)) => text.push_str(&format!(
+            " with a {} BTC fee ({} sat/vB)",
+            btc_trimmed(fee),
+            fee_rate
+        )),
+        _ => text.push_str(" with an unknown fee"),
+    }
+
+    // Change is implied by a consolidation, which only pays the wallet
+    if summary.tx_type != "consolidation" && summary.change_amount > Amount::ZERO {
+        text.push_str(&format!(
+            ", plus {} BTC change",
+            btc_trimmed(summary.change_amount)
+        ));
+    }
+    text.push('.');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Network;
+
+    use crate::test_util::{key_source, p2pkh, p2wpkh, psbt, pubkey, txout};
+    use crate::tests::summarize;
+    use crate::{script_to_address, ParseOptions};
+
+    #[test]
+    fn explains_a_payment_with_change() {
+        let mut psbt = psbt(
+            &[txout(100_000, p2wpkh(1))],
+            &[txout(60_000, p2pkh(2)), txout(38_560, p2wpkh(3))],
+        );
+        psbt.outputs[1]
+            .bip32_derivation
+            .insert(pubkey(3), key_source(1, "m/84'/0'/0'/1/0"));
+        let options = ParseOptions {
+            explain: true,
+            ..Default::default()
+        };
+
+        let recipient = script_to_address(&p2pkh(2), Network::Bitcoin).unwrap();
+        assert_eq!(
+            summarize(&psbt, &options).summary_text.unwrap(),
+            format!(
+                "Sending 0.0006 BTC to {} with a 0.0000144 BTC fee (10 sat/vB), plus 0.0003856 BTC change.",
+                recipient
+            )
+        );
+    }
+}
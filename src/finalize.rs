@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+
+use bitcoin::blockdata::opcodes;
+use bitcoin::blockdata::script::{Builder, Script};
+use bitcoin::util::psbt::{Input, PartiallySignedTransaction};
+use bitcoin::PublicKey;
+
+use crate::script::multisig_template;
+use crate::{is_finalized, resolve_utxo};
+
+/// The signature made by the key `script` pays to, for single-key scripts.
+fn single_key_signature(
+    partial_sigs: &BTreeMap<PublicKey, Vec<u8>>,
+    script: &Script,
+) -> Option<(PublicKey, Vec<u8>)> {
+    partial_sigs
+        .iter()
+        .find(|(pubkey, _)| {
+            Script::new_p2pkh(&pubkey.pubkey_hash()) == *script
+                || pubkey
+                    .wpubkey_hash()
+                    .is_some_and(|hash| Script::new_v0_wpkh(&hash) == *script)
+        })
+        .map(|(pubkey, signature)| (*pubkey, signature.clone()))
+}
+
+/// The first `m` signatures of a multisig `script`, in the order of its keys.
+fn multisig_signatures(
+    partial_sigs: &BTreeMap<PublicKey, Vec<u8>>,
+    script: &Script,
+) -> Option<Vec<Vec<u8>>> {
+    let (multisig, keys) = multisig_template(script)?;
+    let signatures: Vec<Vec<u8>> = keys
+        .iter()
+        .filter_map(|key| partial_sigs.get(&PublicKey::from_slice(key).ok()?))
+        .take(multisig.m)
+        .cloned()
+        .collect();
+    (signatures.len() == multisig.m).then_some(signatures)
+}
+
+/// Pushes each of `items` onto a scriptSig, empty items as `OP_0`.
+fn push_all(items: &[Vec<u8>]) -> Script {
+    items
+        .iter()
+        .fold(Builder::new(), |builder, item| {
+            if item.is_empty() {
+                builder.push_opcode(opcodes::all::OP_PUSHBYTES_0)
+            } else {
+                builder.push_slice(item)
+            }
+        })
+        .into_script()
+}
+
+/// Assembles the final scriptSig and witness of `input` from its partial
+/// signatures, returning whether they were complete.
+fn finalize_input(input: &mut Input, script_pubkey: &Script) -> bool {
+    // A nested script is revealed by pushing it in the scriptSig
+    let (script, redeem_push) = if script_pubkey.is_p2sh() {
+        match &input.redeem_script {
+            Some(redeem_script) => (redeem_script.clone(), vec![redeem_script.to_bytes()]),
+            None => return false,
+        }
+    } else {
+        (script_pubkey.clone(), vec![])
+    };
+
+    let (script_sig, witness) = if script.is_v0_p2wpkh() {
+        match single_key_signature(&input.partial_sigs, &script) {
+            Some((pubkey, signature)) => (redeem_push, Some(vec![signature, pubkey.to_bytes()])),
+            None => return false,
+        }
+    } else if script.is_v0_p2wsh() {
+        let witness_script = match &input.witness_script {
+            Some(witness_script) => witness_script,
+            None => return false,
+        };
+        match multisig_signatures(&input.partial_sigs, witness_script) {
+            Some(signatures) => {
+                // The extra element consumed by OP_CHECKMULTISIG
+                let mut witness = vec![vec![]];
+                witness.extend(signatures);
+                witness.push(witness_script.to_bytes());
+                (redeem_push, Some(witness))
+            }
+            None => return false,
+        }
+    } else if script.is_p2pkh() {
+        match single_key_signature(&input.partial_sigs, &script) {
+            Some((pubkey, signature)) => (vec![signature, pubkey.to_bytes()], None),
+            None => return false,
+        }
+    } else {
+        match multisig_signatures(&input.partial_sigs, &script) {
+            Some(signatures) => {
+                let mut script_sig = vec![vec![]];
+                script_sig.extend(signatures);
+                script_sig.extend(redeem_push);
+                (script_sig, None)
+            }
+            None => return false,
+        }
+    };
+
+    input.final_script_sig = (!script_sig.is_empty()).then(|| push_all(&script_sig));
+    input.final_script_witness = witness;
+
+    // Signing data is no longer needed once the input is final
+    input.partial_sigs.clear();
+    input.sighash_type = None;
+    input.redeem_script = None;
+    input.witness_script = None;
+    input.bip32_derivation.clear();
+    true
+}
+
+/// Finalizes every input of `psbt` spending a P2PKH, P2WPKH or multisig
+/// script, natively or nested in P2SH, that has all the signatures it needs.
+/// Returns the inputs that couldn't be finalized.
+pub(crate) fn finalize(psbt: &mut PartiallySignedTransaction) -> Vec<usize> {
+    let tx = &psbt.global.unsigned_tx;
+    let mut unfinalized = Vec::new();
+    for (index, (input, txin)) in psbt.inputs.iter_mut().zip(&tx.input).enumerate() {
+        if is_finalized(input) {
+            continue;
+        }
+        let script_pubkey = match resolve_utxo(input, &txin.previous_output) {
+            Some(utxo) => utxo.script_pubkey.clone(),
+            None => {
+                unfinalized.push(index);
+                continue;
+            }
+        };
+        if !finalize_input(input, &script_pubkey) {
+            unfinalized.push(index);
+        }
+    }
+    unfinalized
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::consensus::encode::deserialize;
+    use bitcoin::util::psbt::PartiallySignedTransaction;
+
+    use crate::test_util::{multisig, p2wpkh, psbt, pubkey, sign, txout};
+    use crate::tests::summarize;
+    use crate::ParseOptions;
+
+    /// A PSBT spending a 2-of-2 P2WSH output, signed by the keys of `seeds`.
+    fn two_of_two(seeds: &[u8]) -> PartiallySignedTransaction {
+        let witness_script = multisig(2, 2);
+        let mut psbt = psbt(
+            &[txout(10_000, witness_script.to_v0_p2wsh())],
+            &[txout(9_000, p2wpkh(3))],
+        );
+        psbt.inputs[0].witness_script = Some(witness_script);
+        for seed in seeds {
+            sign(&mut psbt, 0, *seed);
+        }
+        psbt
+    }
+
+    fn finalize_options() -> ParseOptions {
+        ParseOptions {
+            finalize: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn finalizes_a_signed_two_of_two() {
+        let psbt = two_of_two(&[1, 2]);
+        let finalization = summarize(&psbt, &finalize_options()).finalization.unwrap();
+        assert!(finalization.finalized);
+        assert!(finalization.unfinalized_inputs.is_empty());
+
+        let finalized: PartiallySignedTransaction =
+            deserialize(&base64::decode(&finalization.psbt).unwrap()).unwrap();
+        let input = &finalized.inputs[0];
+        let signatures = &psbt.inputs[0].partial_sigs;
+        assert_eq!(
+            input.final_script_witness.as_ref().unwrap(),
+            &vec![
+                vec![],
+                signatures[&pubkey(1)].clone(),
+                signatures[&pubkey(2)].clone(),
+                multisig(2, 2).to_bytes(),
+            ]
+        );
+        assert!(input.final_script_sig.is_none());
+        assert!(input.partial_sigs.is_empty());
+        assert!(input.witness_script.is_none());
+    }
+
+    #[test]
+    fn leaves_an_incomplete_input_unfinalized() {
+        let finalization = summarize(&two_of_two(&[1]), &finalize_options())
+            .finalization
+            .unwrap();
+        assert!(!finalization.finalized);
+        assert_eq!(finalization.unfinalized_inputs, vec![0]);
+    }
+}
@@ -0,0 +1,1017 @@
+use bitcoin::{Amount, Network};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use lambda_http::http::header::{
+    HeaderValue, ACCEPT, ACCEPT_ENCODING, ACCESS_CONTROL_ALLOW_HEADERS,
+    ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_ENCODING, CONTENT_TYPE,
+    VARY,
+};
+use lambda_http::http::Method;
+use lambda_http::request::RequestContext;
+use lambda_http::{Body, Error, Request, RequestExt, Response};
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::io::{Read, Write};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+use crate::{
+    blocking, parse_minimal_async, parse_network, parse_psbt_async, parse_psbt_bytes_async,
+    parse_request_networks, Detail, Encoding, FeeBuckets, MinimalSummary, ParseError, ParseOptions,
+    ParsePsbtRequest, PsbtSummary, SummaryCache, DEFAULT_MAX_PSBT_SIZE,
+};
+
+/// How many times the PSBT size limit a gzipped body may inflate to.
+const MAX_INFLATION: usize = 16;
+
+/// The smallest response body worth compressing, in bytes.
+const MIN_COMPRESSED_SIZE: usize = 1024;
+
+/// How many clients the rate limiter tracks at most.
+const MAX_RATE_LIMITED_CLIENTS: usize = 10_000;
+
+/// The rate limit key shared by requests with no known client address.
+const UNIDENTIFIED_CLIENT: &str = "unknown";
+
+/// How long a request may take to parse by default, in milliseconds.
+const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 5000;
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ErrorResponse {
+    error: String,
+    error_code: &'static str,
+}
+
+/// The JSON Schemas of a successful response, in full and minimal detail,
+/// and of an error.
+#[derive(Debug, Serialize)]
+struct SchemaResponse {
+    summary: RootSchema,
+    minimal_summary: RootSchema,
+    error: RootSchema,
+}
+
+#[derive(Debug, Serialize)]
+struct HealthResponse {
+    status: &'static str,
+}
+
+/// A summary in the detail a request asked for.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum Summary {
+    Full(Box<PsbtSummary>),
+    Minimal(MinimalSummary),
+}
+
+/// The outcome of parsing one PSBT of a batch request.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum BatchResult {
+    Ok(Summary),
+    Error(ErrorResponse),
+}
+
+/// Answers with `body` as JSON, indented when `pretty` is set.
+fn json_response<T: Serialize>(status: u16, body: &T, pretty: bool) -> Response<Body> {
+    let json = if pretty {
+        serde_json::to_string_pretty(body)
+    } else {
+        serde_json::to_string(body)
+    };
+    let (status, json) = match json {
+        Ok(json) => (status, json),
+        Err(e) => {
+            let error = ErrorResponse {
+                error: format!("Failed to serialize response: {}", e),
+                error_code: "INTERNAL_ERROR",
+            };
+            (
+                500,
+                serde_json::to_string(&error).unwrap_or_else(|_| String::from("{}")),
+            )
+        }
+    };
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(json))
+        .unwrap()
+}
+
+/// Answers with an error built from `error_code` and `error`, indented when
+/// `pretty` is set.
+fn error_response(
+    status: u16,
+    error_code: &'static str,
+    error: String,
+    pretty: bool,
+) -> Response<Body> {
+    json_response(status, &ErrorResponse { error, error_code }, pretty)
+}
+
+// Everything but an internal failure stems from the submitted PSBT.
+fn parse_error_status(e: &ParseError) -> u16 {
+    match e {
+        ParseError::Base64(_)
+        | ParseError::Hex(_)
+        | ParseError::UnsupportedPsbtVersion { .. }
+        | ParseError::Deserialize(_)
+        | ParseError::LooksLikeRawTx
+        | ParseError::InvalidRawTx(_)
+        | ParseError::PrevoutCountMismatch { .. }
+        | ParseError::InvalidPrevoutScript { .. }
+        | ParseError::InputAmountCountMismatch { .. }
+        | ParseError::NoInputs
+        | ParseError::NoOutputs
+        | ParseError::DuplicateInput { .. }
+        | ParseError::UtxoMismatch { .. }
+        | ParseError::VoutOutOfRange { .. }
+        | ParseError::AmountOverflow { .. }
+        | ParseError::FeeUnderflow { .. }
+        | ParseError::NetworkNotAllowed { .. }
+        | ParseError::InputOutOfRange { .. }
+        | ParseError::NoPsbts
+        | ParseError::IncompatiblePsbts { .. }
+        | ParseError::InvalidTransaction(_) => 400,
+        ParseError::Internal(_) => 500,
+        ParseError::PsbtTooLarge { .. } => 413,
+    }
+}
+
+fn parse_failure(e: &ParseError) -> ErrorResponse {
+    ErrorResponse {
+        error: format!("Failed to parse PSBT: {}", e),
+        error_code: e.code(),
+    }
+}
+
+fn invalid_request(e: serde_json::Error) -> ErrorResponse {
+    ErrorResponse {
+        error: format!("Invalid request: {}", e),
+        error_code: "INVALID_REQUEST",
+    }
+}
+
+/// Parses each request of a batch on its own, so one bad PSBT doesn't fail
+/// the others.
+async fn parse_batch(requests: Vec<serde_json::Value>) -> Vec<BatchResult> {
+    let mut results = Vec::with_capacity(requests.len());
+    for request in requests {
+        let mut request: ParsePsbtRequest = match serde_json::from_value(request) {
+            Ok(request) => request,
+            Err(e) => {
+                results.push(BatchResult::Error(invalid_request(e)));
+                continue;
+            }
+        };
+        apply_config(&mut request.options);
+        let result = if request.options.detail == Detail::Minimal {
+            traced(parse_minimal_async(request))
+                .await
+                .map(Summary::Minimal)
+        } else {
+            traced(parse_full(request))
+                .await
+                .map(|summary| Summary::Full(Box::new(summary)))
+        };
+        results.push(match result {
+            Ok(summary) => BatchResult::Ok(summary),
+            Err(e) => BatchResult::Error(parse_failure(&e)),
+        });
+    }
+    results
+}
+
+/// Summarizes `request` for each of its networks, answering with a map from
+/// network name to a batch-like `{ "ok": <summary> }` or `{ "error": <error> }`.
+async fn parse_networks(request: ParsePsbtRequest, pretty: bool) -> Response<Body> {
+    let detail = request.options.detail;
+    match traced(blocking(move || parse_request_networks(&request))).await {
+        Ok(results) => {
+            let results: BTreeMap<String, BatchResult> = results
+                .into_iter()
+                .map(|(network, result)| {
+                    let result = match result {
+                        Ok(summary) if detail == Detail::Minimal => {
+                            BatchResult::Ok(Summary::Minimal((&summary).into()))
+                        }
+                        Ok(summary) => BatchResult::Ok(Summary::Full(Box::new(summary))),
+                        Err(e) => BatchResult::Error(parse_failure(&e)),
+                    };
+                    (network, result)
+                })
+                .collect();
+            json_response(200, &results, pretty)
+        }
+        Err(e) => json_response(parse_error_status(&e), &parse_failure(&e), pretty),
+    }
+}
+
+/// Answers CORS preflight requests and adds the CORS headers to every other
+/// response, so the endpoint can be called from a browser.
+///
+/// The allowed origin is read from `CORS_ALLOW_ORIGIN`, defaulting to `*`,
+/// and the function won't start when it isn't a valid header value.
+///
+/// `GET /healthz` and `GET /` answer load-balancer health checks without
+/// parsing anything, and `GET /schema` describes the response shapes.
+///
+/// Responses are indented when the query has `pretty=true`, or a JSON
+/// request sets `"pretty": true`.
+///
+/// Requests still being parsed after `REQUEST_TIMEOUT_MS`, 5 seconds by
+/// default, are answered with a 504 `TIMEOUT` error.
+///
+/// When `RATE_LIMIT_PER_MINUTE` is set, parse requests beyond that rate
+/// from one client address are answered with a 429 `RATE_LIMITED` error.
+///
+/// Responses of at least `MIN_COMPRESSED_SIZE` bytes are gzipped for
+/// requests whose `Accept-Encoding` includes gzip.
+pub async fn function_handler(event: Request) -> Result<Response<Body>, Error> {
+    let pretty = event.query_string_parameters().first("pretty") == Some("true");
+    let accepts_gzip = accepts_gzip(&event);
+    let mut response = if event.method() == Method::OPTIONS {
+        Response::builder().status(204).body(Body::Empty).unwrap()
+    } else if is_health_check(&event) {
+        json_response(200, &HealthResponse { status: "ok" }, pretty)
+    } else if event.method() == Method::GET && event.uri().path() == "/schema" {
+        json_response(
+            200,
+            &SchemaResponse {
+                summary: schema_for!(PsbtSummary),
+                minimal_summary: schema_for!(MinimalSummary),
+                error: schema_for!(ErrorResponse),
+            },
+            pretty,
+        )
+    } else if !rate_limit_allows(&event) {
+        error_response(
+            429,
+            "RATE_LIMITED",
+            String::from("Too many requests, try again later"),
+            pretty,
+        )
+    } else {
+        let timeout = request_timeout();
+        match tokio::time::timeout(timeout, handle_request(event, pretty)).await {
+            Ok(response) => response?,
+            Err(_) => error_response(
+                504,
+                "TIMEOUT",
+                format!("Parsing took longer than {} ms", timeout.as_millis()),
+                pretty,
+            ),
+        }
+    };
+
+    if accepts_gzip {
+        response = gzip_response(response);
+    }
+    // Any body might have been gzipped for another Accept-Encoding, so caches
+    // have to tell the two apart
+    if !matches!(response.body(), Body::Empty) {
+        response
+            .headers_mut()
+            .insert(VARY, HeaderValue::from_static("Accept-Encoding"));
+    }
+
+    let headers = response.headers_mut();
+    // An invalid origin stops the function at startup, see `allowed_origin`
+    if let Ok(origin) = allowed_origin() {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+    }
+    headers.insert(
+        ACCESS_CONTROL_ALLOW_METHODS,
+        HeaderValue::from_static("GET, POST, OPTIONS"),
+    );
+    headers.insert(
+        ACCESS_CONTROL_ALLOW_HEADERS,
+        HeaderValue::from_static("Content-Type, Content-Encoding"),
+    );
+    Ok(response)
+}
+
+/// Gzips the body of `response` once it's large enough to be worth it,
+/// leaving it as is should compressing fail.
+fn gzip_response(response: Response<Body>) -> Response<Body> {
+    let (mut parts, body) = response.into_parts();
+    let bytes: &[u8] = match &body {
+        Body::Text(text) => text.as_bytes(),
+        Body::Binary(bytes) => bytes,
+        Body::Empty => &[],
+    };
+    if bytes.len() < MIN_COMPRESSED_SIZE {
+        return Response::from_parts(parts, body);
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    match encoder.write_all(bytes).and_then(|()| encoder.finish()) {
+        Ok(compressed) => {
+            parts
+                .headers
+                .insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+            Response::from_parts(parts, Body::Binary(compressed))
+        }
+        Err(_) => Response::from_parts(parts, body),
+    }
+}
+
+/// Whether `event`'s Accept-Encoding lists gzip with a nonzero quality. A
+/// quality of 0 marks the coding as unacceptable, see RFC 9110 12.5.3.
+fn accepts_gzip(event: &Request) -> bool {
+    let value = match event
+        .headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(value) => value,
+        None => return false,
+    };
+    value.split(',').any(|coding| {
+        let mut params = coding.split(';');
+        let name = params.next().unwrap_or_default();
+        let quality = params
+            .filter_map(|param| param.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+            .map_or(Some(1.0), |(_, q)| q.trim().parse::<f32>().ok());
+        name.trim().eq_ignore_ascii_case("gzip") && quality.is_some_and(|q| q > 0.0)
+    })
+}
+
+/// A token bucket per client, refilled continuously at `per_minute` tokens a
+/// minute up to that many. Lambda instances don't share it, so the limit
+/// only holds per instance.
+struct RateLimiter {
+    per_minute: u32,
+    max_clients: usize,
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter tracking up to `max_clients` clients.
+    fn new(per_minute: u32, max_clients: usize) -> Self {
+        RateLimiter {
+            per_minute,
+            max_clients,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token from the bucket of `client`, if there's one left.
+    fn allow(&self, client: &str) -> bool {
+        let capacity = f64::from(self.per_minute);
+        let now = Instant::now();
+        let refill = |(tokens, updated): &(f64, Instant)| {
+            let elapsed = now.duration_since(*updated).as_secs_f64();
+            (tokens + elapsed * capacity / 60.0).min(capacity)
+        };
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        // A full bucket is no different from a missing one, so those are the
+        // ones to drop once too many clients are tracked, and failing that
+        // the one idle the longest
+        if buckets.len() >= self.max_clients && !buckets.contains_key(client) {
+            buckets.retain(|_, bucket| refill(bucket) < capacity);
+            if buckets.len() >= self.max_clients {
+                let oldest = buckets
+                    .iter()
+                    .min_by_key(|(_, (_, updated))| *updated)
+                    .map(|(client, _)| client.clone());
+                if let Some(oldest) = oldest {
+                    buckets.remove(&oldest);
+                }
+            }
+        }
+        let bucket = buckets.entry(client.to_owned()).or_insert((capacity, now));
+        let tokens = refill(bucket);
+        if tokens < 1.0 {
+            return false;
+        }
+        *bucket = (tokens - 1.0, now);
+        true
+    }
+}
+
+/// The rate limiter allowing `RATE_LIMIT_PER_MINUTE` requests a minute per
+/// client, or none when that's unset or zero.
+fn rate_limiter() -> Option<&'static RateLimiter> {
+    static LIMITER: OnceLock<Option<RateLimiter>> = OnceLock::new();
+    LIMITER
+        .get_or_init(|| {
+            let per_minute: u32 = std::env::var("RATE_LIMIT_PER_MINUTE").ok()?.parse().ok()?;
+            (per_minute > 0).then(|| RateLimiter::new(per_minute, MAX_RATE_LIMITED_CLIENTS))
+        })
+        .as_ref()
+}
+
+/// Whether the rate limit lets `event` through. Requests whose client can't
+/// be told apart share one bucket.
+fn rate_limit_allows(event: &Request) -> bool {
+    match rate_limiter() {
+        Some(limiter) => limiter.allow(
+            client_address(event)
+                .as_deref()
+                .unwrap_or(UNIDENTIFIED_CLIENT),
+        ),
+        None => true,
+    }
+}
+
+/// The address `event` came from: the source IP in API Gateway's request
+/// context, or else the last `X-Forwarded-For` hop, the one added by the
+/// proxy in front of the function. The earlier hops are whatever the client
+/// sent, so they can't be trusted.
+fn client_address(event: &Request) -> Option<String> {
+    let source_ip = match event.extensions().get::<RequestContext>() {
+        Some(RequestContext::ApiGatewayV1(context)) => context.identity.source_ip.clone(),
+        Some(RequestContext::ApiGatewayV2(context)) => context.http.source_ip.clone(),
+        Some(RequestContext::WebSocket(context)) => context.identity.source_ip.clone(),
+        Some(RequestContext::Alb(_)) | None => None,
+    };
+    source_ip.filter(|ip| !ip.is_empty()).or_else(|| {
+        event
+            .headers()
+            .get("X-Forwarded-For")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .map(str::trim)
+            .filter(|client| !client.is_empty())
+            .map(str::to_owned)
+    })
+}
+
+fn is_health_check(event: &Request) -> bool {
+    let path = event.uri().path();
+    event.method() == Method::GET
+        && (path == "/healthz"
+            || (path == "/" && event.query_string_parameters().first("psbt").is_none()))
+}
+
+/// Decompresses a gzipped request body, refusing to inflate it past `limit`
+/// bytes. Failures carry the status to answer with.
+fn gunzip(body: &[u8], limit: usize) -> Result<Vec<u8>, (u16, ErrorResponse)> {
+    let mut inflated = Vec::new();
+    GzDecoder::new(body)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut inflated)
+        .map_err(|e| {
+            (
+                400,
+                ErrorResponse {
+                    error: format!("Request body is not valid gzip: {}", e),
+                    error_code: "INVALID_ENCODING",
+                },
+            )
+        })?;
+    if inflated.len() > limit {
+        return Err((
+            413,
+            ErrorResponse {
+                error: format!("Request body inflates to more than {} bytes", limit),
+                error_code: "BODY_TOO_LARGE",
+            },
+        ));
+    }
+    Ok(inflated)
+}
+
+/// Parses the optional `network` query parameter.
+fn query_network(network: Option<&str>) -> Result<Option<Network>, ErrorResponse> {
+    network
+        .map(parse_network)
+        .transpose()
+        .map_err(|e| ErrorResponse {
+            error: format!("Invalid request: {}", e),
+            error_code: "INVALID_REQUEST",
+        })
+}
+
+/// The options raw PSBT bytes are parsed with, which only come from the
+/// `network` query parameter and the environment.
+fn binary_options(network: Option<&str>) -> Result<ParseOptions, ErrorResponse> {
+    let mut options = ParseOptions {
+        network: query_network(network)?,
+        ..Default::default()
+    };
+    apply_config(&mut options);
+    Ok(options)
+}
+
+/// Answers for a body that isn't a JSON request but came without a
+/// `Content-Type`, parsing it as raw PSBT bytes instead. When that fails too
+/// both errors are reported.
+async fn untyped_response(
+    body: &[u8],
+    network: Option<&str>,
+    json_error: serde_json::Error,
+    csv: bool,
+    pretty: bool,
+) -> Response<Body> {
+    let options = match binary_options(network) {
+        Ok(options) => options,
+        Err(e) => return json_response(400, &e, pretty),
+    };
+    match traced(parse_psbt_bytes_async(body.to_vec(), options)).await {
+        Ok(summary) => summary_response(Ok(summary), csv, pretty),
+        Err(e) => json_response(
+            400,
+            &ErrorResponse {
+                error: format!(
+                    "Invalid request: body is neither a JSON request ({}) nor a binary PSBT ({})",
+                    json_error, e
+                ),
+                error_code: "INVALID_REQUEST",
+            },
+            pretty,
+        ),
+    }
+}
+
+/// Summarizes `request` in full, through the summary cache when one is
+/// configured.
+async fn parse_full(request: ParsePsbtRequest) -> Result<PsbtSummary, ParseError> {
+    match summary_cache() {
+        Some(cache) => blocking(move || cache.parse(&request)).await,
+        None => parse_psbt_async(request).await,
+    }
+}
+
+/// A parse result whose shape is logged once parsing succeeds.
+trait Parsed {
+    fn log(&self, elapsed_us: u64);
+}
+
+impl Parsed for PsbtSummary {
+    fn log(&self, elapsed_us: u64) {
+        tracing::info!(
+            inputs = self.inputs.len(),
+            outputs = self.pay_to_info.len(),
+            fee = self.fee.map(Amount::as_sat),
+            elapsed_us,
+            "parsed PSBT"
+        );
+    }
+}
+
+impl Parsed for MinimalSummary {
+    fn log(&self, elapsed_us: u64) {
+        tracing::info!(
+            outputs = self.output_count,
+            fee = self.fee.map(Amount::as_sat),
+            elapsed_us,
+            "parsed PSBT"
+        );
+    }
+}
+
+/// Logs the summary or error of each network, as a request naming several
+/// gets one per network.
+impl Parsed for BTreeMap<String, Result<PsbtSummary, ParseError>> {
+    fn log(&self, elapsed_us: u64) {
+        for (network, result) in self {
+            match result {
+                Ok(summary) => tracing::info!(
+                    network = %network,
+                    inputs = summary.inputs.len(),
+                    outputs = summary.pay_to_info.len(),
+                    fee = summary.fee.map(Amount::as_sat),
+                    elapsed_us,
+                    "parsed PSBT"
+                ),
+                Err(e) => tracing::error!(
+                    network = %network,
+                    error_code = e.code(),
+                    elapsed_us,
+                    "failed to parse PSBT"
+                ),
+            }
+        }
+    }
+}
+
+/// Runs `parse` in a span, logging the shape and fee of the PSBT or the code
+/// of the error, along with the time taken.
+async fn traced<T, F>(parse: F) -> Result<T, ParseError>
+where
+    T: Parsed,
+    F: Future<Output = Result<T, ParseError>>,
+{
+    let span = tracing::info_span!("parse_psbt");
+    let start = Instant::now();
+    let result = parse.instrument(span.clone()).await;
+    let elapsed_us = start.elapsed().as_micros() as u64;
+    let _span = span.entered();
+    match &result {
+        Ok(parsed) => parsed.log(elapsed_us),
+        Err(e) => tracing::error!(error_code = e.code(), elapsed_us, "failed to parse PSBT"),
+    }
+    result
+}
+
+/// Lists the outputs of `summary` as CSV rows, followed by a row for the fee.
+fn csv_response(summary: &PsbtSummary) -> Response<Body> {
+    let mut csv = String::from("index,address,amount_sat,is_change,script_type\n");
+    for (index, output) in summary.pay_to_info.iter().enumerate() {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            index,
+            output.pay_to.as_deref().unwrap_or(""),
+            output.amount.as_sat(),
+            output.is_change,
+            output.script_type
+        ));
+    }
+    // A fee that couldn't be worked out is left blank
+    let fee = summary
+        .fee
+        .map(|fee| fee.as_sat().to_string())
+        .unwrap_or_default();
+    csv.push_str(&format!("fee,,{},,\n", fee));
+    Response::builder()
+        .status(200)
+        .header("Content-Type", "text/csv")
+        .body(Body::from(csv))
+        .unwrap()
+}
+
+/// Answers with the summary, as CSV when `csv` is set, or the error as JSON,
+/// indented when `pretty` is set.
+fn summary_response(
+    result: Result<PsbtSummary, ParseError>,
+    csv: bool,
+    pretty: bool,
+) -> Response<Body> {
+    match result {
+        Ok(summary) if csv => csv_response(&summary),
+        Ok(summary) => json_response(200, &summary, pretty),
+        Err(e) => json_response(parse_error_status(&e), &parse_failure(&e), pretty),
+    }
+}
+
+/// Applies the limits configured through the environment, which requests
+/// can't override.
+fn apply_config(options: &mut ParseOptions) {
+    options.max_psbt_size = Some(max_psbt_size());
+    // An unknown network stops the function at startup, see `allowed_networks`
+    options.allowed_networks = allowed_networks().ok().flatten().cloned();
+    options.fee_buckets = Some(fee_buckets());
+}
+
+/// The fee bucket thresholds, overridable through `FEE_BUCKETS` as the
+/// comma separated medium, high and extreme fee rates in sat/vB. A value
+/// that isn't three ascending rates is ignored.
+fn fee_buckets() -> FeeBuckets {
+    static BUCKETS: OnceLock<FeeBuckets> = OnceLock::new();
+    *BUCKETS.get_or_init(|| {
+        std::env::var("FEE_BUCKETS")
+            .ok()
+            .and_then(|rates| {
+                let rates: Vec<f64> = rates
+                    .split(',')
+                    .map(|rate| rate.trim().parse().ok())
+                    .collect::<Option<_>>()?;
+                match rates[..] {
+                    [medium, high, extreme] if medium <= high && high <= extreme => {
+                        Some(FeeBuckets {
+                            medium,
+                            high,
+                            extreme,
+                        })
+                    }
+                    _ => None,
+                }
+            })
+            .unwrap_or_default()
+    })
+}
+
+/// The networks PSBTs may resolve to, read once from a comma separated
+/// `ALLOWED_NETWORKS` and any network when unset, or the error naming a
+/// network that isn't known. Checked at startup, so a typo can't silently
+/// narrow the list.
+pub fn allowed_networks() -> Result<Option<&'static Vec<Network>>, &'static str> {
+    static ALLOWED: OnceLock<Result<Option<Vec<Network>>, String>> = OnceLock::new();
+    ALLOWED
+        .get_or_init(|| match std::env::var("ALLOWED_NETWORKS") {
+            Ok(names) => names
+                .split(',')
+                .map(|name| parse_network(name.trim()))
+                .collect::<Result<_, _>>()
+                .map(Some)
+                .map_err(|e| format!("ALLOWED_NETWORKS: {}", e)),
+            Err(_) => Ok(None),
+        })
+        .as_ref()
+        .map(Option::as_ref)
+        .map_err(String::as_str)
+}
+
+/// The PSBT size limit, overridable through `MAX_PSBT_SIZE`.
+fn max_psbt_size() -> usize {
+    static MAX: OnceLock<usize> = OnceLock::new();
+    *MAX.get_or_init(|| {
+        std::env::var("MAX_PSBT_SIZE")
+            .ok()
+            .and_then(|max| max.parse().ok())
+            .unwrap_or(DEFAULT_MAX_PSBT_SIZE)
+    })
+}
+
+/// The cache of recent summaries, holding up to `SUMMARY_CACHE_SIZE` of
+/// them, or none when that's unset or zero.
+fn summary_cache() -> Option<&'static SummaryCache> {
+    static CACHE: OnceLock<Option<SummaryCache>> = OnceLock::new();
+    CACHE
+        .get_or_init(|| {
+            let capacity: usize = std::env::var("SUMMARY_CACHE_SIZE").ok()?.parse().ok()?;
+            (capacity > 0).then(|| SummaryCache::new(capacity))
+        })
+        .as_ref()
+}
+
+/// How long a request may take to parse, overridable through
+/// `REQUEST_TIMEOUT_MS`.
+fn request_timeout() -> Duration {
+    static TIMEOUT: OnceLock<Duration> = OnceLock::new();
+    *TIMEOUT.get_or_init(|| {
+        let millis = std::env::var("REQUEST_TIMEOUT_MS")
+            .ok()
+            .and_then(|millis| millis.parse().ok())
+            .unwrap_or(DEFAULT_REQUEST_TIMEOUT_MS);
+        Duration::from_millis(millis)
+    })
+}
+
+/// The origin allowed to call the function, read once from
+/// `CORS_ALLOW_ORIGIN` and `*` when unset, or the error describing a value
+/// that can't be sent as a header. Checked at startup, so the function
+/// refuses to run rather than answer with some other origin.
+pub fn allowed_origin() -> Result<&'static HeaderValue, &'static str> {
+    static ORIGIN: OnceLock<Result<HeaderValue, String>> = OnceLock::new();
+    ORIGIN
+        .get_or_init(|| match std::env::var("CORS_ALLOW_ORIGIN") {
+            Ok(origin) => HeaderValue::from_str(&origin)
+                .map_err(|_| format!("CORS_ALLOW_ORIGIN {:?} isn't a valid header value", origin)),
+            Err(_) => Ok(HeaderValue::from_static("*")),
+        })
+        .as_ref()
+        .map_err(String::as_str)
+}
+
+/// Parses the PSBT in a JSON request body, answering with a JSON summary or error.
+///
+/// A GET request is answered for the base64 PSBT in its `psbt` query
+/// parameter. A body sent as `application/octet-stream` is parsed as the raw
+/// PSBT bytes, with the network taken from the `network` query parameter.
+///
+/// The outputs of a single PSBT are listed as CSV instead when the query has
+/// `format=csv` or the request accepts `text/csv`.
+///
+/// A body holding a JSON array is treated as a batch of requests and answered
+/// with an array of `{ "ok": <summary> }` or `{ "error": <error> }` results.
+/// A request listing `networks` is answered with such a result per network.
+///
+/// A body sent without a `Content-Type` is read as JSON, and as raw PSBT
+/// bytes when it isn't JSON.
+///
+/// Bodies sent with `Content-Encoding: gzip` are decompressed first.
+async fn handle_request(event: Request, pretty: bool) -> Result<Response<Body>, Error> {
+    let is_gzip = event
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("gzip"));
+    let content_type = event
+        .headers()
+        .get(CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap_or_default());
+    let is_binary = content_type.is_some_and(|value| value.starts_with("application/octet-stream"));
+    let is_untyped = content_type.is_none();
+    let accepts_csv = event
+        .headers()
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("text/csv"));
+    let method = event.method().clone();
+    let query = event.query_string_parameters();
+    let csv = accepts_csv || query.first("format") == Some("csv");
+    let raw_body = event.into_body();
+    let body = if is_gzip {
+        match gunzip(&raw_body, max_psbt_size().saturating_mul(MAX_INFLATION)) {
+            Ok(body) => Cow::Owned(body),
+            Err((status, e)) => return Ok(json_response(status, &e, pretty)),
+        }
+    } else {
+        Cow::Borrowed(&raw_body[..])
+    };
+
+    // GET requests carry the base64 PSBT in the query string, where an
+    // unescaped `+` has been decoded to a space
+    if method == Method::GET {
+        let psbt = match query.first("psbt") {
+            Some(psbt) => psbt.replace(' ', "+"),
+            None => {
+                return Ok(error_response(
+                    400,
+                    "INVALID_REQUEST",
+                    String::from("Invalid request: missing query parameter `psbt`"),
+                    pretty,
+                ))
+            }
+        };
+        let network = match query_network(query.first("network")) {
+            Ok(network) => network,
+            Err(e) => return Ok(json_response(400, &e, pretty)),
+        };
+        let mut request = ParsePsbtRequest {
+            psbt,
+            encoding: Encoding::Base64,
+            options: ParseOptions {
+                network,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        apply_config(&mut request.options);
+        return Ok(summary_response(
+            traced(parse_full(request)).await,
+            csv,
+            pretty,
+        ));
+    }
+
+    if body.is_empty() {
+        return Ok(error_response(
+            400,
+            "EMPTY_BODY",
+            String::from("Request body is empty"),
+            pretty,
+        ));
+    }
+
+    // Raw PSBT bytes take their options from the query string
+    if is_binary {
+        let options = match binary_options(query.first("network")) {
+            Ok(options) => options,
+            Err(e) => return Ok(json_response(400, &e, pretty)),
+        };
+        return Ok(summary_response(
+            traced(parse_psbt_bytes_async(body.into_owned(), options)).await,
+            csv,
+            pretty,
+        ));
+    }
+
+    let is_batch = body.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[');
+    if is_batch {
+        return Ok(match serde_json::from_slice(&body) {
+            Ok(requests) => json_response(200, &parse_batch(requests).await, pretty),
+            Err(e) => json_response(400, &invalid_request(e), pretty),
+        });
+    }
+
+    let mut request: ParsePsbtRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        // A body sent without a Content-Type may just as well be a raw PSBT
+        Err(e) if is_untyped => {
+            return Ok(untyped_response(&body, query.first("network"), e, csv, pretty).await)
+        }
+        Err(e) => return Ok(json_response(400, &invalid_request(e), pretty)),
+    };
+    apply_config(&mut request.options);
+    let pretty = pretty || request.pretty;
+
+    if request.networks.is_some() {
+        return Ok(parse_networks(request, pretty).await);
+    }
+
+    // A minimal summary has no outputs to list as CSV
+    if request.options.detail == Detail::Minimal {
+        return Ok(match traced(parse_minimal_async(request)).await {
+            Ok(summary) => json_response(200, &summary, pretty),
+            Err(e) => json_response(parse_error_status(&e), &parse_failure(&e), pretty),
+        });
+    }
+
+    Ok(summary_response(
+        traced(parse_full(request)).await,
+        csv,
+        pretty,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Address;
+
+    use super::*;
+    use crate::test_util::{p2pkh, p2wpkh, psbt, txout};
+    use crate::tests::summarize;
+
+    fn request(forwarded_for: Option<&str>) -> Request {
+        let mut builder = lambda_http::http::Request::builder().uri("/");
+        if let Some(forwarded_for) = forwarded_for {
+            builder = builder.header("X-Forwarded-For", forwarded_for);
+        }
+        builder.body(Body::Empty).unwrap()
+    }
+
+    #[test]
+    fn client_address_prefers_the_source_ip() {
+        let context: RequestContext = serde_json::from_value(serde_json::json!({
+            "accountId": "123456789012",
+            "apiId": "api",
+            "stage": "$default",
+            "requestId": "id",
+            "time": "",
+            "timeEpoch": 0,
+            "http": {
+                "method": "POST",
+                "path": "/",
+                "protocol": "HTTP/1.1",
+                "sourceIp": "203.0.113.7",
+                "userAgent": "test"
+            }
+        }))
+        .unwrap();
+        let event = request(Some("198.51.100.1")).with_request_context(context);
+        assert_eq!(client_address(&event).as_deref(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    fn client_address_takes_the_last_forwarded_hop() {
+        let event = request(Some("10.0.0.1, 198.51.100.1, 203.0.113.7"));
+        assert_eq!(client_address(&event).as_deref(), Some("203.0.113.7"));
+        assert_eq!(client_address(&request(Some(" "))), None);
+        assert_eq!(client_address(&request(None)), None);
+    }
+
+    #[test]
+    fn accepts_gzip_unless_its_quality_is_zero() {
+        let accepts = |value: &str| {
+            let event = lambda_http::http::Request::builder()
+                .header(ACCEPT_ENCODING, value)
+                .body(Body::Empty)
+                .unwrap();
+            accepts_gzip(&event)
+        };
+        assert!(accepts("gzip"));
+        assert!(accepts("br, GZIP;q=0.5"));
+        assert!(!accepts("gzip;q=0, identity"));
+        assert!(!accepts("gzip; q=0.000"));
+        assert!(!accepts("identity"));
+        assert!(!accepts_gzip(&request(None)));
+    }
+
+    #[test]
+    fn csv_lists_each_output_and_the_fee() {
+        let psbt = psbt(
+            &[txout(100_000, p2wpkh(1))],
+            &[txout(60_000, p2pkh(2)), txout(38_560, p2wpkh(3))],
+        );
+        let summary = summarize(&psbt, &ParseOptions::default());
+        let response = csv_response(&summary);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/csv");
+        let address = |script| Address::from_script(&script, Network::Bitcoin).unwrap();
+        assert_eq!(
+            std::str::from_utf8(response.body()).unwrap(),
+            format!(
+                "index,address,amount_sat,is_change,script_type\n\
+                 0,{},60000,false,p2pkh\n\
+                 1,{},38560,false,p2wpkh\n\
+                 fee,,1440,,\n",
+                address(p2pkh(2)),
+                address(p2wpkh(3))
+            )
+        );
+    }
+
+    #[test]
+    fn rate_limiter_refuses_a_request_past_the_limit() {
+        let limiter = RateLimiter::new(2, 10);
+        assert!(limiter.allow("a"));
+        assert!(limiter.allow("a"));
+        assert!(!limiter.allow("a"));
+        assert!(limiter.allow("b"));
+    }
+
+    #[test]
+    fn rate_limiter_tracks_at_most_max_clients() {
+        let limiter = RateLimiter::new(1, 2);
+        assert!(limiter.allow("a"));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(limiter.allow("b"));
+        // Neither bucket has refilled, so the one idle the longest goes
+        assert!(limiter.allow("c"));
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert!(!buckets.contains_key("a"));
+    }
+}
@@ -0,0 +1,2646 @@
+use bitcoin::blockdata::transaction::{OutPoint, SigHashType, Transaction, TxOut};
+use bitcoin::consensus::encode::{deserialize, serialize};
+use bitcoin::hashes::hex::{FromHex, ToHex};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::util::bip143::SigHashCache;
+use bitcoin::util::bip32::{ChildNumber, DerivationPath, Fingerprint, KeySource};
+use bitcoin::util::psbt::raw::{Key, ProprietaryKey};
+use bitcoin::util::psbt::{Input, PartiallySignedTransaction};
+use bitcoin::Network;
+use bitcoin::{Amount, Denomination, PublicKey, Script};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::OnceLock;
+
+/// The largest decoded PSBT accepted by default, in bytes.
+pub const DEFAULT_MAX_PSBT_SIZE: usize = 100 * 1024;
+
+/// The PSBT version this crate can parse.
+const SUPPORTED_PSBT_VERSION: u32 = 0;
+
+/// Key type of the global PSBT_GLOBAL_VERSION field.
+const PSBT_GLOBAL_VERSION: u8 = 0xfb;
+
+/// The most satoshis that will ever exist.
+const MAX_MONEY: u64 = 21_000_000 * 100_000_000;
+
+/// nSequence values below this signal BIP125 replaceability.
+const RBF_SEQUENCE_THRESHOLD: u32 = 0xffff_fffe;
+
+/// The nSequence bit that disables BIP68 relative locktimes.
+const SEQUENCE_LOCKTIME_DISABLE_FLAG: u32 = 1 << 31;
+
+/// The nSequence bit that makes a BIP68 relative locktime time based.
+const SEQUENCE_LOCKTIME_TYPE_FLAG: u32 = 1 << 22;
+
+/// nLockTime values below this are block heights, the rest are timestamps.
+const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+mod cache;
+mod combine;
+mod diff;
+mod error;
+mod explain;
+mod finalize;
+mod script;
+mod signatures;
+mod summary;
+mod taproot;
+#[cfg(test)]
+mod test_util;
+mod validate;
+mod weight;
+
+pub use cache::SummaryCache;
+pub use combine::combine_psbts;
+pub use diff::diff_psbts;
+pub use error::{ParseError, UnknownNetwork};
+pub use script::{address_encoding, classify_script, script_to_address};
+use script::{multisig_threshold, op_return_data};
+pub use summary::{
+    DebugSighash, Derivation, DiffResult, Extraction, Finalization, GlobalXpub, InputDetail,
+    InvalidSignature, MinimalSummary, Multisig, OutputDetail, PsbtSummary, RelativeLocktime,
+    ResolutionError, TaprootSpend, UnknownField, UnknownFields, Warning,
+};
+pub use validate::DEFAULT_HIGH_FEE_RATE;
+pub use weight::estimate_final_vsize;
+
+/// AWS Lambda handler exposing `parse_psbt` over HTTP.
+#[cfg(feature = "lambda")]
+pub mod lambda;
+
+/// Serializes an optional network as its string name, e.g. `"testnet"`.
+pub fn serialize_network<S>(network: &Option<Network>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match network {
+        Some(n) => serializer.serialize_str(&n.to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Serializes the given input amounts as numbers of satoshis.
+fn serialize_input_amounts<S>(
+    amounts: &Option<Vec<Option<Amount>>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    amounts
+        .as_ref()
+        .map(|amounts| {
+            amounts
+                .iter()
+                .map(|amount| amount.map(Amount::as_sat))
+                .collect::<Vec<_>>()
+        })
+        .serialize(serializer)
+}
+
+/// Deserializes the given input amounts from numbers of satoshis.
+fn deserialize_input_amounts<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<Option<Amount>>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(
+        Option::<Vec<Option<u64>>>::deserialize(deserializer)?.map(|amounts| {
+            amounts
+                .into_iter()
+                .map(|amount| amount.map(Amount::from_sat))
+                .collect()
+        }),
+    )
+}
+
+/// A network as given in a request, by name or by number.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NetworkId {
+    Name(String),
+    Number(u32),
+}
+
+/// Deserializes an optional network from its string name, or from a number:
+/// either an id from 0 for mainnet through 1 for testnet, 2 for signet and 3
+/// for regtest, or the network's protocol magic.
+pub fn deserialize_network<'de, D>(deserializer: D) -> Result<Option<Network>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<NetworkId>::deserialize(deserializer)? {
+        Some(NetworkId::Name(s)) => Ok(Some(parse_network(&s).map_err(serde::de::Error::custom)?)),
+        Some(NetworkId::Number(number)) => {
+            let network = match number {
+                0 => Some(Network::Bitcoin),
+                1 => Some(Network::Testnet),
+                2 => Some(Network::Signet),
+                3 => Some(Network::Regtest),
+                magic => Network::from_magic(magic),
+            };
+            network.map(Some).ok_or_else(|| {
+                serde::de::Error::custom(format!(
+                    "unknown network number {}, expected 0 to 3 or a network magic",
+                    number
+                ))
+            })
+        }
+        None => Ok(None),
+    }
+}
+
+/// Serializes an optional list of networks as their string names.
+pub fn serialize_networks<S>(
+    networks: &Option<Vec<Network>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match networks {
+        Some(networks) => serializer.collect_seq(networks.iter().map(|n| n.to_string())),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserializes an optional list of networks, each given by name or number
+/// like `deserialize_network` accepts.
+pub fn deserialize_networks<'de, D>(deserializer: D) -> Result<Option<Vec<Network>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Item(#[serde(deserialize_with = "deserialize_network")] Option<Network>);
+
+    Option::<Vec<Item>>::deserialize(deserializer)?
+        .map(|items| {
+            items
+                .into_iter()
+                .map(|Item(network)| {
+                    network.ok_or_else(|| serde::de::Error::custom("network can't be null"))
+                })
+                .collect()
+        })
+        .transpose()
+}
+
+/// Parses a network name, case-insensitively. Accepts `bitcoin` or `mainnet`,
+/// `testnet`, `signet` and `regtest`.
+pub fn parse_network(name: &str) -> Result<Network, UnknownNetwork> {
+    match name.to_ascii_lowercase().as_str() {
+        "bitcoin" | "mainnet" => Ok(Network::Bitcoin),
+        "testnet" => Ok(Network::Testnet),
+        "signet" => Ok(Network::Signet),
+        "regtest" => Ok(Network::Regtest),
+        _ => Err(UnknownNetwork(name.to_owned())),
+    }
+}
+
+/// The network assumed when a request names none and none can be detected,
+/// read once from `DEFAULT_NETWORK` and mainnet when unset. An unknown
+/// `DEFAULT_NETWORK` also counts as mainnet here; `try_default_network`
+/// reports it instead, so a service can refuse to start.
+pub fn default_network() -> Network {
+    try_default_network().unwrap_or(Network::Bitcoin)
+}
+
+/// The network assumed when a request names none, see `default_network`,
+/// or the error naming an unknown `DEFAULT_NETWORK`.
+pub fn try_default_network() -> Result<Network, UnknownNetwork> {
+    static DEFAULT: OnceLock<Result<Network, UnknownNetwork>> = OnceLock::new();
+    DEFAULT
+        .get_or_init(|| match std::env::var("DEFAULT_NETWORK") {
+            Ok(name) => parse_network(&name),
+            Err(_) => Ok(Network::Bitcoin),
+        })
+        .clone()
+}
+
+/// The text encoding a PSBT is submitted in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    #[default]
+    Base64,
+    Hex,
+}
+
+/// What a request's payload holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadType {
+    #[default]
+    Psbt,
+    /// A serialized network transaction, always hex encoded.
+    RawTx,
+}
+
+/// An output spent by a raw transaction's input, given alongside it since
+/// the transaction itself doesn't carry the values it spends.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Prevout {
+    #[serde(
+        serialize_with = "summary::serialize_sat",
+        deserialize_with = "summary::deserialize_sat"
+    )]
+    pub amount: Amount,
+    /// The output's script, as hex.
+    pub script_pubkey: String,
+}
+
+/// A request to parse an encoded PSBT, or a raw transaction.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ParsePsbtRequest {
+    #[serde(alias = "tx")]
+    pub psbt: String,
+    #[serde(default)]
+    pub encoding: Encoding,
+    #[serde(default, rename = "type")]
+    pub payload_type: PayloadType,
+    /// The outputs spent by a raw transaction's inputs, in input order.
+    #[serde(default)]
+    pub prevouts: Option<Vec<Prevout>>,
+    /// Summarize the payload once for each of these networks instead, see
+    /// `parse_request_networks`.
+    #[serde(
+        default,
+        serialize_with = "serialize_networks",
+        deserialize_with = "deserialize_networks"
+    )]
+    pub networks: Option<Vec<Network>>,
+    /// Indent the JSON response. Only the HTTP handler reads this.
+    #[serde(default)]
+    pub pretty: bool,
+    #[serde(flatten)]
+    pub options: ParseOptions,
+}
+
+/// Options controlling how a PSBT is summarized.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ParseOptions {
+    #[serde(
+        default,
+        serialize_with = "serialize_network",
+        deserialize_with = "deserialize_network"
+    )]
+    pub network: Option<Network>,
+    /// Run the sanity checks and report their findings as warnings.
+    #[serde(default)]
+    pub validate: bool,
+    /// The fee rate, in sat/vB, above which validation warns.
+    #[serde(default)]
+    pub high_fee_rate: Option<f64>,
+    /// Verify each partial signature against its input's sighash, which is
+    /// comparatively slow.
+    #[serde(default)]
+    pub verify_signatures: bool,
+    /// Finalize the inputs whose signatures are complete and return the
+    /// finalized PSBT.
+    #[serde(default)]
+    pub finalize: bool,
+    /// Extract the broadcastable transaction once every input is finalized,
+    /// after finalizing when that was requested too.
+    #[serde(default)]
+    pub extract: bool,
+    /// Compute the sighash the signer of one input should have signed, to
+    /// debug signatures that don't verify.
+    #[serde(default)]
+    pub debug_sighash: Option<DebugSighashRequest>,
+    /// Describe the transaction in a plain sentence, for reviewers who
+    /// don't read the structured fields.
+    #[serde(default)]
+    pub explain: bool,
+    /// Render the largest payment as a BIP21 `bitcoin:` URI.
+    #[serde(default)]
+    pub emit_bip21: bool,
+    /// How much of the summary to return.
+    #[serde(default)]
+    pub detail: Detail,
+    /// The values of the UTXOs spent, one per input, filling in those the
+    /// PSBT carries no UTXO data for. Null entries leave an input as is.
+    #[serde(
+        default,
+        serialize_with = "serialize_input_amounts",
+        deserialize_with = "deserialize_input_amounts"
+    )]
+    pub input_amounts: Option<Vec<Option<Amount>>>,
+    /// The largest decoded PSBT accepted, `DEFAULT_MAX_PSBT_SIZE` when unset.
+    /// This is never taken from a request body.
+    #[serde(skip)]
+    pub max_psbt_size: Option<usize>,
+    /// The networks a PSBT may resolve to, any when unset. This is never
+    /// taken from a request body.
+    #[serde(skip)]
+    pub allowed_networks: Option<Vec<Network>>,
+    /// The fee rates separating the fee buckets, `FeeBuckets::default()`
+    /// when unset. This is never taken from a request body.
+    #[serde(skip)]
+    pub fee_buckets: Option<FeeBuckets>,
+}
+
+/// How much of a summary to return.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Detail {
+    #[default]
+    Full,
+    /// Just the txid, fee and output totals, see `MinimalSummary`.
+    Minimal,
+}
+
+/// The input whose sighash to compute.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct DebugSighashRequest {
+    pub input: usize,
+}
+
+/// The fee rates, in sat/vB, at which a fee stops counting as low, medium
+/// and high respectively.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeBuckets {
+    pub medium: f64,
+    pub high: f64,
+    pub extreme: f64,
+}
+
+impl Default for FeeBuckets {
+    fn default() -> Self {
+        FeeBuckets {
+            medium: 5.0,
+            high: 30.0,
+            extreme: 200.0,
+        }
+    }
+}
+
+impl FeeBuckets {
+    /// Names the bucket `fee_rate` falls in, each threshold belonging to the
+    /// bucket above it.
+    pub fn classify(&self, fee_rate: f64) -> &'static str {
+        if fee_rate < self.medium {
+            "low"
+        } else if fee_rate < self.high {
+            "medium"
+        } else if fee_rate < self.extreme {
+            "high"
+        } else {
+            "extreme"
+        }
+    }
+}
+
+/// Looks up the output spent by `input`. Legacy inputs are resolved through
+/// the full previous transaction, as BIP174 requires, and segwit inputs
+/// through their witness UTXO when given.
+fn resolve_utxo<'a>(input: &'a Input, prevout: &OutPoint) -> Option<&'a TxOut> {
+    let full = input
+        .non_witness_utxo
+        .as_ref()
+        .and_then(|prev_tx| prev_tx.output.get(prevout.vout as usize));
+    match (full, &input.witness_utxo) {
+        (Some(utxo), _) if !spends_witness_program(input, Some(utxo)) => Some(utxo),
+        (_, Some(witness_utxo)) => Some(witness_utxo),
+        (full, None) => full,
+    }
+}
+
+/// Checks that each input's UTXO data describes the output it spends: a
+/// non-witness UTXO must be the transaction named by the outpoint and have
+/// the output spent, and a witness UTXO must pay to a witness program,
+/// natively or nested in P2SH.
+fn check_utxos(psbt: &PartiallySignedTransaction) -> Result<(), ParseError> {
+    for (index, (input, txin)) in psbt
+        .inputs
+        .iter()
+        .zip(&psbt.global.unsigned_tx.input)
+        .enumerate()
+    {
+        if let Some(prev_tx) = &input.non_witness_utxo {
+            if prev_tx.txid() != txin.previous_output.txid {
+                return Err(ParseError::UtxoMismatch {
+                    input: index,
+                    reason: "non-witness UTXO is not the transaction the input spends from",
+                });
+            }
+            // Otherwise the input would be dropped from the fee unnoticed
+            let vout = txin.previous_output.vout;
+            if vout as usize >= prev_tx.output.len() {
+                return Err(ParseError::VoutOutOfRange {
+                    input: index,
+                    vout,
+                    outputs: prev_tx.output.len(),
+                });
+            }
+        }
+        if let Some(utxo) = &input.witness_utxo {
+            // A P2SH output is only known to wrap a witness program once
+            // the redeem script is given
+            let script = &utxo.script_pubkey;
+            let segwit = script.is_witness_program()
+                || (script.is_p2sh()
+                    && input
+                        .redeem_script
+                        .as_ref()
+                        .is_none_or(|redeem_script| redeem_script.is_witness_program()));
+            if !segwit {
+                return Err(ParseError::UtxoMismatch {
+                    input: index,
+                    reason: "witness UTXO given for an input that isn't segwit",
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether `input` already carries its final scriptSig or witness.
+fn is_finalized(input: &Input) -> bool {
+    input.final_script_sig.is_some() || input.final_script_witness.is_some()
+}
+
+/// The multisig threshold of the script `input` is spent with. A nested
+/// segwit input's redeem script is just the witness program, so the witness
+/// script takes precedence.
+fn input_multisig(input: &Input) -> Option<Multisig> {
+    input
+        .witness_script
+        .as_ref()
+        .or(input.redeem_script.as_ref())
+        .and_then(multisig_threshold)
+}
+
+/// Whether `input` carries the signatures its script needs: a Taproot key
+/// signature, or as many partial signatures as its multisig threshold, one
+/// for single key scripts.
+fn has_enough_signatures(input: &Input) -> bool {
+    taproot::has_tap_key_sig(input)
+        || input.partial_sigs.len() >= input_multisig(input).map_or(1, |multisig| multisig.m)
+}
+
+/// Sums up how far signing has come: `unsigned` while no input carries a
+/// signature, `complete` once every input is finalized or has the
+/// signatures its threshold needs, single key inputs needing one, and
+/// `partially_signed` in between.
+fn signing_status(psbt: &PartiallySignedTransaction) -> &'static str {
+    let complete = psbt
+        .inputs
+        .iter()
+        .all(|input| is_finalized(input) || has_enough_signatures(input));
+    let signed = psbt.inputs.iter().any(|input| {
+        is_finalized(input) || taproot::has_tap_key_sig(input) || !input.partial_sigs.is_empty()
+    });
+    if complete {
+        "complete"
+    } else if signed {
+        "partially_signed"
+    } else {
+        "unsigned"
+    }
+}
+
+/// Extracts the finalized transaction, or notes which inputs still lack
+/// their final scriptSig or witness.
+fn extract(psbt: &PartiallySignedTransaction) -> Extraction {
+    let unfinalized: Vec<String> = psbt
+        .inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| !is_finalized(input))
+        .map(|(index, _)| index.to_string())
+        .collect();
+    if !unfinalized.is_empty() {
+        return Extraction {
+            final_tx_hex: None,
+            txid: None,
+            vsize: None,
+            note: Some(Warning::new(
+                "NOT_FINALIZED",
+                format!("inputs not yet finalized: {}", unfinalized.join(", ")),
+            )),
+        };
+    }
+
+    let tx = psbt.clone().extract_tx();
+    Extraction {
+        final_tx_hex: Some(serialize(&tx).to_hex()),
+        txid: Some(tx.txid().to_string()),
+        vsize: Some(tx.get_weight().div_ceil(4)),
+        note: None,
+    }
+}
+
+/// Lists the key origins of an input or output map.
+fn derivations(bip32_derivation: &BTreeMap<PublicKey, KeySource>) -> Vec<Derivation> {
+    bip32_derivation
+        .iter()
+        .map(|(pubkey, (fingerprint, path))| Derivation {
+            pubkey: pubkey.to_string(),
+            master_fingerprint: fingerprint.to_string(),
+            derivation_path: path.to_string(),
+        })
+        .collect()
+}
+
+/// Adds up the amounts of the inputs or outputs, which can't exceed the
+/// supply of bitcoin.
+fn sum_amounts(
+    mut amounts: impl Iterator<Item = Amount>,
+    side: &'static str,
+) -> Result<Amount, ParseError> {
+    amounts
+        .try_fold(Amount::ZERO, |total, amount| total.checked_add(amount))
+        .filter(|total| total.as_sat() <= MAX_MONEY)
+        .ok_or(ParseError::AmountOverflow { side })
+}
+
+/// Hashes the fields that identify a summary, in a fixed line based format
+/// that doesn't depend on how the summary is serialized.
+///
+/// The format is versioned so it only changes deliberately: the txid, then
+/// each input's outpoint and amount and each output's script and amount,
+/// both sorted, then the fee.
+fn summary_hash(summary: &PsbtSummary, tx: &Transaction) -> String {
+    let mut inputs: Vec<String> = summary
+        .inputs
+        .iter()
+        .map(|input| match input.amount {
+            Some(amount) => format!(
+                "input {}:{} {}",
+                input.prev_txid,
+                input.vout,
+                amount.as_sat()
+            ),
+            None => format!("input {}:{} -", input.prev_txid, input.vout),
+        })
+        .collect();
+    inputs.sort();
+    let mut outputs: Vec<String> = tx
+        .output
+        .iter()
+        .map(|output| {
+            format!(
+                "output {} {}",
+                output.script_pubkey.as_bytes().to_hex(),
+                output.value
+            )
+        })
+        .collect();
+    outputs.sort();
+
+    let mut canonical = format!("psbt-summary-v1\ntxid {}\n", summary.txid);
+    for line in inputs.iter().chain(&outputs) {
+        canonical.push_str(line);
+        canonical.push('\n');
+    }
+    match summary.fee {
+        Some(fee) => canonical.push_str(&format!("fee {}\n", fee.as_sat())),
+        None => canonical.push_str("fee -\n"),
+    }
+    sha256::Hash::hash(canonical.as_bytes()).to_hex()
+}
+
+/// Formats `amount` in BTC with 8 fractional digits.
+fn btc_string(amount: Amount) -> String {
+    amount.to_string_in(Denomination::Bitcoin)
+}
+
+/// Formats `amount` in BTC without trailing zeros, e.g. `0.01`.
+fn btc_trimmed(amount: Amount) -> String {
+    let btc = btc_string(amount);
+    btc.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+/// Lists the unknown and proprietary pairs of one PSBT map.
+fn unknown_fields(
+    unknown: &BTreeMap<Key, Vec<u8>>,
+    proprietary: &BTreeMap<ProprietaryKey, Vec<u8>>,
+) -> Vec<UnknownField> {
+    let proprietary = proprietary.iter().map(|(key, value)| (key.to_key(), value));
+    unknown
+        .iter()
+        .map(|(key, value)| (key.clone(), value))
+        .chain(proprietary)
+        .map(|(key, value)| UnknownField {
+            key: [&[key.type_value][..], &key.key].concat().to_hex(),
+            value: value.to_hex(),
+        })
+        .collect()
+}
+
+/// Reads a compact size integer off the front of `bytes`.
+fn read_compact_size(bytes: &mut &[u8]) -> Option<usize> {
+    let (&first, rest) = bytes.split_first()?;
+    let width = match first {
+        0xfd => 2,
+        0xfe => 4,
+        0xff => 8,
+        n => {
+            *bytes = rest;
+            return Some(n as usize);
+        }
+    };
+    let (value, rest) = (rest.get(..width)?, &rest[width..]);
+    *bytes = rest;
+    let mut buf = [0; 8];
+    buf[..width].copy_from_slice(value);
+    usize::try_from(u64::from_le_bytes(buf)).ok()
+}
+
+/// Splits `len` bytes off the front of `bytes`.
+fn read_bytes<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    let value = bytes.get(..len)?;
+    *bytes = &bytes[len..];
+    Some(value)
+}
+
+/// Scans the raw global map for the PSBT version, which `bitcoin` refuses to
+/// deserialize beyond version 0. Returns `None` when no version is declared
+/// or the map is malformed, which deserializing then reports.
+fn declared_psbt_version(psbt_bytes: &[u8]) -> Option<u32> {
+    let mut bytes = psbt_bytes.strip_prefix(b"psbt\xff")?;
+    loop {
+        let key_len = read_compact_size(&mut bytes)?;
+        if key_len == 0 {
+            return None;
+        }
+        let key = read_bytes(&mut bytes, key_len)?;
+        let value_len = read_compact_size(&mut bytes)?;
+        let value = read_bytes(&mut bytes, value_len)?;
+        if key == [PSBT_GLOBAL_VERSION] {
+            return Some(u32::from_le_bytes(value.try_into().ok()?));
+        }
+    }
+}
+
+/// Whether `input` is spent with a witness, judging by its final witness or
+/// the script of the UTXO it spends.
+fn spends_witness_program(input: &Input, utxo: Option<&TxOut>) -> bool {
+    let nested = input
+        .redeem_script
+        .as_ref()
+        .is_some_and(|script| script.is_witness_program());
+    input.final_script_witness.is_some()
+        || utxo.is_some_and(|utxo| {
+            utxo.script_pubkey.is_witness_program() || (utxo.script_pubkey.is_p2sh() && nested)
+        })
+}
+
+/// Flags the outputs that pay back to the signer's own wallet.
+///
+/// An output is change when it carries BIP32 derivation info from the same
+/// master fingerprint as one of the inputs. If the inputs carry no
+/// derivation info, any derivation info on an output marks it as change.
+fn change_outputs(psbt: &PartiallySignedTransaction) -> Vec<bool> {
+    let input_fingerprints: BTreeSet<Fingerprint> = psbt
+        .inputs
+        .iter()
+        .flat_map(|input| input.bip32_derivation.values())
+        .map(|(fingerprint, _)| *fingerprint)
+        .collect();
+
+    psbt.outputs
+        .iter()
+        .map(|output| {
+            if input_fingerprints.is_empty() {
+                !output.bip32_derivation.is_empty()
+            } else {
+                output
+                    .bip32_derivation
+                    .values()
+                    .any(|(fingerprint, _)| input_fingerprints.contains(fingerprint))
+            }
+        })
+        .collect()
+}
+
+/// Decodes the BIP68 relative locktime of a `sequence`, which only
+/// transactions of version 2 or later enforce.
+fn relative_locktime(version: i32, sequence: u32) -> Option<RelativeLocktime> {
+    if version < 2 || sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+        return None;
+    }
+    let value = (sequence & 0xffff) as u16;
+    Some(if sequence & SEQUENCE_LOCKTIME_TYPE_FLAG != 0 {
+        RelativeLocktime {
+            lock_type: "time".to_owned(),
+            value,
+            seconds: Some(u32::from(value) * 512),
+        }
+    } else {
+        RelativeLocktime {
+            lock_type: "blocks".to_owned(),
+            value,
+            seconds: None,
+        }
+    })
+}
+
+/// Guesses the network a PSBT was built for.
+///
+/// Output scripts carry no network information, so this relies on the
+/// version bytes of global xpubs and on the BIP44 coin type (`0'` for mainnet,
+/// `1'` for the test networks) of any BIP32 derivation paths.
+fn detect_network(psbt: &PartiallySignedTransaction) -> Option<Network> {
+    if let Some(xpub) = psbt.global.xpub.keys().next() {
+        return Some(xpub.network);
+    }
+
+    psbt.inputs
+        .iter()
+        .flat_map(|input| input.bip32_derivation.values())
+        .chain(
+            psbt.outputs
+                .iter()
+                .flat_map(|output| output.bip32_derivation.values()),
+        )
+        .find_map(|(_, path)| coin_type_network(path))
+}
+
+/// The network named by the BIP44 coin type of `path`, testnet standing in
+/// for every test network.
+fn coin_type_network(path: &DerivationPath) -> Option<Network> {
+    match path.as_ref().get(1)? {
+        ChildNumber::Hardened { index: 0 } => Some(Network::Bitcoin),
+        ChildNumber::Hardened { index: 1 } => Some(Network::Testnet),
+        _ => None,
+    }
+}
+
+/// Decodes a base64 PSBT and summarizes its transaction.
+///
+/// Addresses are rendered for `network`. When it is `None` the network is
+/// detected from the PSBT's keys, falling back to `default_network()`.
+pub fn parse_psbt(base64_psbt: &str, network: Option<Network>) -> Result<PsbtSummary, ParseError> {
+    let decoded_psbt = decode_base64(base64_psbt)?;
+    parse_psbt_bytes(
+        &decoded_psbt,
+        &ParseOptions {
+            network,
+            ..Default::default()
+        },
+    )
+}
+
+/// Decodes a hex raw transaction and summarizes it like a PSBT whose inputs
+/// are all finalized.
+///
+/// The transaction doesn't say what its inputs spend, so the fee and input
+/// details are only filled in when `prevouts` gives the spent outputs, one
+/// per input.
+pub fn parse_raw_tx(
+    tx_hex: &str,
+    network: Option<Network>,
+    prevouts: Option<&[Prevout]>,
+) -> Result<PsbtSummary, ParseError> {
+    raw_tx_summary(
+        &Vec::<u8>::from_hex(tx_hex)?,
+        prevouts,
+        &ParseOptions {
+            network,
+            ..Default::default()
+        },
+    )
+}
+
+/// Summarizes the PSBT or raw transaction in `request`, decoding a PSBT with
+/// the declared encoding.
+pub fn parse_request(request: &ParsePsbtRequest) -> Result<PsbtSummary, ParseError> {
+    parse_payload(request, &decode_payload(request)?, &request.options)
+}
+
+/// Summarizes the PSBT or raw transaction in `request` once for each of
+/// `request.networks`, so an ambiguous network can be judged from the
+/// addresses each one renders. The results are keyed by network name, each
+/// failing on its own.
+pub fn parse_request_networks(
+    request: &ParsePsbtRequest,
+) -> Result<BTreeMap<String, Result<PsbtSummary, ParseError>>, ParseError> {
+    let payload = decode_payload(request)?;
+    Ok(request
+        .networks
+        .iter()
+        .flatten()
+        .map(|network| {
+            let options = ParseOptions {
+                network: Some(*network),
+                ..request.options.clone()
+            };
+            let summary = parse_payload(request, &payload, &options);
+            (network.to_string(), summary)
+        })
+        .collect())
+}
+
+/// Decodes the PSBT in `request` with its declared encoding, or the hex of
+/// its raw transaction.
+fn decode_payload(request: &ParsePsbtRequest) -> Result<Vec<u8>, ParseError> {
+    Ok(match (request.payload_type, request.encoding) {
+        (PayloadType::RawTx, _) | (PayloadType::Psbt, Encoding::Hex) => {
+            Vec::<u8>::from_hex(&request.psbt)?
+        }
+        (PayloadType::Psbt, Encoding::Base64) => decode_base64(&request.psbt)?,
+    })
+}
+
+/// Summarizes the decoded payload of `request` with `options`.
+fn parse_payload(
+    request: &ParsePsbtRequest,
+    payload: &[u8],
+    options: &ParseOptions,
+) -> Result<PsbtSummary, ParseError> {
+    match request.payload_type {
+        PayloadType::Psbt => parse_psbt_bytes(payload, options),
+        PayloadType::RawTx => raw_tx_summary(payload, request.prevouts.as_deref(), options),
+    }
+}
+
+/// Summarizes just the fee and outputs of the PSBT or raw transaction in
+/// `request`, skipping the per-input and per-output details.
+pub fn parse_request_minimal(request: &ParsePsbtRequest) -> Result<MinimalSummary, ParseError> {
+    // A raw transaction has no UTXO data to skip resolving
+    if request.payload_type == PayloadType::RawTx {
+        return parse_request(request).map(|summary| (&summary).into());
+    }
+    let psbt = decode_psbt(&decode_payload(request)?, request.options.max_psbt_size)?;
+    let utxos = resolve_utxos(&psbt)?;
+    effective_network(&psbt, &request.options)?;
+
+    let tx = &psbt.global.unsigned_tx;
+    let fee = compute_fee(&psbt, &utxos, &input_amounts(&utxos, &request.options)?)?;
+    Ok(MinimalSummary {
+        txid: tx.txid().to_string(),
+        fee: fee.fee,
+        fee_rate_sat_vb: fee.fee_rate_sat_vb,
+        total_output_amount: fee.output_amount,
+        output_count: tx.output.len(),
+    })
+}
+
+/// Decodes standard base64, falling back to the URL-safe alphabet used by
+/// PSBTs passed through URLs. Whitespace, such as the line breaks of a PSBT
+/// copied from a terminal or email, is ignored.
+fn decode_base64(input: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    let compact: String;
+    let input = if input.bytes().any(|b| b.is_ascii_whitespace()) {
+        compact = input.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+        &compact
+    } else {
+        input
+    };
+    base64::decode(input).or_else(|e| base64::decode_config(input, base64::URL_SAFE).map_err(|_| e))
+}
+
+/// Summarizes the PSBT in `request` on tokio's blocking thread pool, so the
+/// slower options such as signature verification don't stall the runtime.
+#[cfg(feature = "async")]
+pub async fn parse_psbt_async(request: ParsePsbtRequest) -> Result<PsbtSummary, ParseError> {
+    blocking(move || parse_request(&request)).await
+}
+
+/// Summarizes just the fee and outputs of the PSBT in `request` on tokio's
+/// blocking thread pool, see `parse_request_minimal`.
+#[cfg(feature = "async")]
+pub async fn parse_minimal_async(request: ParsePsbtRequest) -> Result<MinimalSummary, ParseError> {
+    blocking(move || parse_request_minimal(&request)).await
+}
+
+/// Summarizes the binary PSBT `psbt_bytes` on tokio's blocking thread pool,
+/// see `parse_psbt_bytes`.
+#[cfg(feature = "async")]
+pub async fn parse_psbt_bytes_async(
+    psbt_bytes: Vec<u8>,
+    options: ParseOptions,
+) -> Result<PsbtSummary, ParseError> {
+    blocking(move || parse_psbt_bytes(&psbt_bytes, &options)).await
+}
+
+/// Runs `parse` on tokio's blocking thread pool.
+#[cfg(feature = "async")]
+pub(crate) async fn blocking<T, F>(parse: F) -> Result<T, ParseError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ParseError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(parse)
+        .await
+        .map_err(|e| ParseError::Internal(e.to_string()))?
+}
+
+/// Deserializes a binary PSBT no larger than `max_psbt_size`, which defaults
+/// to `DEFAULT_MAX_PSBT_SIZE`.
+fn decode_psbt(
+    psbt_bytes: &[u8],
+    max_psbt_size: Option<usize>,
+) -> Result<PartiallySignedTransaction, ParseError> {
+    // Refuse oversized PSBTs before deserializing them
+    let max = max_psbt_size.unwrap_or(DEFAULT_MAX_PSBT_SIZE);
+    if psbt_bytes.len() > max {
+        return Err(ParseError::PsbtTooLarge {
+            size: psbt_bytes.len(),
+            max,
+        });
+    }
+
+    // Version 2 PSBTs carry no global unsigned transaction, so name the
+    // version instead of failing on the missing transaction
+    if let Some(version) = declared_psbt_version(psbt_bytes) {
+        if version != SUPPORTED_PSBT_VERSION {
+            return Err(ParseError::UnsupportedPsbtVersion { version });
+        }
+    }
+
+    // A raw transaction is an easy mistake to make, so point to the way it
+    // can be summarized instead
+    deserialize(psbt_bytes).map_err(|e| match deserialize::<Transaction>(psbt_bytes) {
+        Ok(_) => ParseError::LooksLikeRawTx,
+        Err(_) => e.into(),
+    })
+}
+
+/// Summarizes a binary serialized PSBT.
+pub fn parse_psbt_bytes(
+    psbt_bytes: &[u8],
+    options: &ParseOptions,
+) -> Result<PsbtSummary, ParseError> {
+    let psbt = decode_psbt(psbt_bytes, options.max_psbt_size)?;
+    let utxos = resolve_utxos(&psbt)?;
+    summarize(&psbt, &utxos, options)
+}
+
+/// Resolves the UTXO spent by each input of `psbt`, once its data is known
+/// to match. Inputs without UTXO data are left unresolved, leaving the fee
+/// unknown unless `options.input_amounts` fills in their values.
+fn resolve_utxos(psbt: &PartiallySignedTransaction) -> Result<Vec<Option<&TxOut>>, ParseError> {
+    check_utxos(psbt)?;
+    Ok(psbt
+        .inputs
+        .iter()
+        .zip(&psbt.global.unsigned_tx.input)
+        .map(|(input, txin)| resolve_utxo(input, &txin.previous_output))
+        .collect())
+}
+
+/// The value spent by each input: its UTXO's, otherwise the one given in
+/// `options.input_amounts`.
+fn input_amounts(
+    utxos: &[Option<&TxOut>],
+    options: &ParseOptions,
+) -> Result<Vec<Option<Amount>>, ParseError> {
+    let overrides = options.input_amounts.as_deref().unwrap_or_default();
+    if options.input_amounts.is_some() && overrides.len() != utxos.len() {
+        return Err(ParseError::InputAmountCountMismatch {
+            inputs: utxos.len(),
+            amounts: overrides.len(),
+        });
+    }
+    Ok(utxos
+        .iter()
+        .enumerate()
+        .map(|(index, utxo)| {
+            utxo.map(|utxo| Amount::from_sat(utxo.value))
+                .or_else(|| overrides.get(index).copied().flatten())
+        })
+        .collect())
+}
+
+/// Summarizes a raw transaction by wrapping it in a PSBT whose inputs are
+/// finalized with its scriptSigs and witnesses.
+fn raw_tx_summary(
+    tx_bytes: &[u8],
+    prevouts: Option<&[Prevout]>,
+    options: &ParseOptions,
+) -> Result<PsbtSummary, ParseError> {
+    let max = options.max_psbt_size.unwrap_or(DEFAULT_MAX_PSBT_SIZE);
+    if tx_bytes.len() > max {
+        return Err(ParseError::PsbtTooLarge {
+            size: tx_bytes.len(),
+            max,
+        });
+    }
+    let tx: Transaction = deserialize(tx_bytes).map_err(ParseError::InvalidRawTx)?;
+
+    let mut unsigned_tx = tx.clone();
+    for txin in &mut unsigned_tx.input {
+        txin.script_sig = Script::new();
+        txin.witness.clear();
+    }
+    let mut psbt = PartiallySignedTransaction::from_unsigned_tx(unsigned_tx)
+        .map_err(|_| ParseError::InvalidTransaction("transaction can't be wrapped in a PSBT"))?;
+    for (input, txin) in psbt.inputs.iter_mut().zip(tx.input) {
+        input.final_script_sig = (!txin.script_sig.is_empty()).then_some(txin.script_sig);
+        input.final_script_witness = (!txin.witness.is_empty()).then_some(txin.witness);
+    }
+
+    let prevouts: Vec<TxOut> = match prevouts {
+        Some(prevouts) => {
+            if prevouts.len() != psbt.inputs.len() {
+                return Err(ParseError::PrevoutCountMismatch {
+                    inputs: psbt.inputs.len(),
+                    prevouts: prevouts.len(),
+                });
+            }
+            prevouts
+                .iter()
+                .enumerate()
+                .map(|(index, prevout)| {
+                    let script_pubkey = Vec::<u8>::from_hex(&prevout.script_pubkey)
+                        .map_err(|error| ParseError::InvalidPrevoutScript { index, error })?;
+                    Ok(TxOut {
+                        value: prevout.amount.as_sat(),
+                        script_pubkey: Script::from(script_pubkey),
+                    })
+                })
+                .collect::<Result<_, ParseError>>()?
+        }
+        None => Vec::new(),
+    };
+    let utxos: Vec<Option<&TxOut>> = if prevouts.is_empty() {
+        vec![None; psbt.inputs.len()]
+    } else {
+        prevouts.iter().map(Some).collect()
+    };
+
+    summarize(&psbt, &utxos, options)
+}
+
+/// Picks the network addresses are rendered for: the requested one,
+/// otherwise the one detected from the PSBT's keys, as long as it's allowed.
+fn effective_network(
+    psbt: &PartiallySignedTransaction,
+    options: &ParseOptions,
+) -> Result<Network, ParseError> {
+    let network = options
+        .network
+        .or_else(|| detect_network(psbt))
+        .unwrap_or_else(default_network);
+    match &options.allowed_networks {
+        Some(allowed) if !allowed.contains(&network) => {
+            Err(ParseError::NetworkNotAllowed { network })
+        }
+        _ => Ok(network),
+    }
+}
+
+/// The amounts, size and fee of a transaction.
+struct Fee {
+    input_amount: Option<Amount>,
+    output_amount: Amount,
+    fee: Option<Amount>,
+    weight: usize,
+    vsize: usize,
+    estimated_final_weight: Option<usize>,
+    fee_rate_sat_vb: Option<f64>,
+    fee_rate_estimated: bool,
+}
+
+/// Works out the fee of the transaction of `psbt`, whose inputs spend
+/// `amounts`, checking that it spends and pays something.
+///
+/// The fee rate is taken over the estimated size of the finalized
+/// transaction, since the unsigned one lacks the scriptSigs and witnesses
+/// that will be paid for, and over the unsigned size when that can't be
+/// estimated.
+fn compute_fee(
+    psbt: &PartiallySignedTransaction,
+    utxos: &[Option<&TxOut>],
+    amounts: &[Option<Amount>],
+) -> Result<Fee, ParseError> {
+    let tx = &psbt.global.unsigned_tx;
+    if tx.input.is_empty() {
+        return Err(ParseError::NoInputs);
+    }
+    if tx.output.is_empty() {
+        return Err(ParseError::NoOutputs);
+    }
+    // Spending an outpoint twice makes the transaction invalid
+    let mut spent = BTreeMap::new();
+    for (index, txin) in tx.input.iter().enumerate() {
+        if let Some(first) = spent.insert(txin.previous_output, index) {
+            return Err(ParseError::DuplicateInput {
+                first,
+                second: index,
+                outpoint: txin.previous_output,
+            });
+        }
+    }
+
+    // Calculate the fee, which needs the value of every input. A coinbase
+    // input has none, its outputs claiming the block's subsidy and fees
+    let input_amount = sum_amounts(amounts.iter().flatten().copied(), "inputs")?;
+    let output_amount = sum_amounts(
+        tx.output
+            .iter()
+            .map(|output| Amount::from_sat(output.value)),
+        "outputs",
+    )?;
+    // The input total is as unknown as the fee while any amount is missing
+    let (input_amount, fee) = if amounts.iter().all(|amount| amount.is_some()) {
+        match input_amount.checked_sub(output_amount) {
+            Some(fee) => (Some(input_amount), Some(fee)),
+            None => {
+                return Err(ParseError::FeeUnderflow {
+                    input_amount,
+                    output_amount,
+                })
+            }
+        }
+    } else {
+        (None, None)
+    };
+
+    // Calculate the fee rate in sat/vB, rounded to two decimals
+    let weight = tx.get_weight();
+    let vsize = weight.div_ceil(4);
+    if vsize == 0 {
+        return Err(ParseError::InvalidTransaction(
+            "transaction has zero virtual size",
+        ));
+    }
+    let estimated_final_weight = weight::estimate_final_weight(weight, &psbt.inputs, utxos);
+    let fee_vsize = estimated_final_weight.map_or(vsize, |weight| weight.div_ceil(4));
+    let fee_rate_sat_vb =
+        fee.map(|fee| (fee.as_sat() as f64 / fee_vsize as f64 * 100.0).round() / 100.0);
+
+    Ok(Fee {
+        input_amount,
+        output_amount,
+        fee,
+        weight,
+        vsize,
+        estimated_final_weight,
+        fee_rate_sat_vb,
+        fee_rate_estimated: estimated_final_weight.is_some(),
+    })
+}
+
+/// Summarizes `psbt`, whose inputs spend `utxos`. The fee is only worked out
+/// when every UTXO is known.
+fn summarize(
+    psbt: &PartiallySignedTransaction,
+    utxos: &[Option<&TxOut>],
+    options: &ParseOptions,
+) -> Result<PsbtSummary, ParseError> {
+    let network = effective_network(psbt, options)?;
+    let amounts = input_amounts(utxos, options)?;
+
+    // Get transaction details from the unsigned transaction, which is present
+    // whether or not the PSBT has been signed or finalized yet
+    let tx = &psbt.global.unsigned_tx;
+    let Fee {
+        input_amount,
+        output_amount,
+        fee,
+        weight,
+        vsize,
+        estimated_final_weight,
+        fee_rate_sat_vb,
+        fee_rate_estimated,
+    } = compute_fee(psbt, utxos, &amounts)?;
+
+    // Get the input addresses
+    let input_addresses: Vec<String> = utxos
+        .iter()
+        .flatten()
+        .filter_map(|utxo| script_to_address(&utxo.script_pubkey, network))
+        .map(|addr| addr.to_string())
+        .collect();
+
+    // Note the inputs whose UTXO is unknown, which coinbase inputs don't have.
+    // An out of range vout was already refused, so only missing data is left
+    let resolution_errors: Vec<ResolutionError> = tx
+        .input
+        .iter()
+        .zip(utxos)
+        .enumerate()
+        .filter(|(_, (txin, utxo))| utxo.is_none() && !txin.previous_output.is_null())
+        .map(|(index, _)| ResolutionError {
+            input_index: index,
+            reason: "NO_UTXO_DATA".to_owned(),
+        })
+        .collect();
+
+    // Get the per-input details
+    let inputs: Vec<InputDetail> = psbt
+        .inputs
+        .iter()
+        .zip(&tx.input)
+        .zip(utxos)
+        .zip(&amounts)
+        .enumerate()
+        .map(|(index, (((input, txin), utxo), amount))| {
+            let finalized = is_finalized(input);
+            let multisig = input_multisig(input);
+            InputDetail {
+                index,
+                prev_txid: txin.previous_output.txid.to_string(),
+                vout: txin.previous_output.vout,
+                sequence: txin.sequence,
+                signals_rbf: txin.sequence < RBF_SEQUENCE_THRESHOLD,
+                is_coinbase: txin.previous_output.is_null(),
+                relative_locktime: relative_locktime(tx.version, txin.sequence),
+                address: utxo
+                    .and_then(|utxo| script_to_address(&utxo.script_pubkey, network))
+                    .map(|address| address.to_string()),
+                amount: *amount,
+                amount_btc: amount.map(btc_string),
+                prev_script_pubkey_hex: utxo.map(|utxo| utxo.script_pubkey.as_bytes().to_hex()),
+                script_type: utxo.map(|utxo| classify_script(&utxo.script_pubkey).to_owned()),
+                has_witness_utxo: input.witness_utxo.is_some(),
+                has_non_witness_utxo: input.non_witness_utxo.is_some(),
+                sighash_type: input.sighash_type.unwrap_or(SigHashType::All).to_string(),
+                partial_sig_count: input.partial_sigs.len(),
+                signers: input
+                    .partial_sigs
+                    .keys()
+                    .map(|pubkey| pubkey.to_string())
+                    .collect(),
+                multisig,
+                redeem_script_hex: input
+                    .redeem_script
+                    .as_ref()
+                    .map(|script| script.as_bytes().to_hex()),
+                witness_script_hex: input
+                    .witness_script
+                    .as_ref()
+                    .map(|script| script.as_bytes().to_hex()),
+                derivations: derivations(&input.bip32_derivation),
+                finalized,
+                needs_signature: !finalized && !has_enough_signatures(input),
+                taproot: taproot::taproot_spend(input, *utxo),
+            }
+        })
+        .collect();
+
+    // Get the send address and total amount from the largest output paying
+    // someone else, unless every output is change
+    let is_change = change_outputs(psbt);
+    let has_payment = is_change.iter().any(|change| !change);
+    let candidates: Vec<(usize, &TxOut)> = tx
+        .output
+        .iter()
+        .zip(&is_change)
+        .enumerate()
+        .filter(|(_, (_, change))| !has_payment || !**change)
+        .map(|(index, (output, _))| (index, output))
+        .collect();
+    // Addresses are only for display, so outputs without one, such as
+    // OP_RETURN data, still count towards the fee and totals
+    let primary = candidates
+        .iter()
+        .filter_map(|(_, output)| {
+            script_to_address(&output.script_pubkey, network)
+                .map(|address| (address.to_string(), output.value))
+        })
+        .max_by_key(|(_, value)| *value);
+    let (send_address, total_amount) = match primary {
+        Some((address, value)) => (Some(address), Amount::from_sat(value)),
+        None => (
+            None,
+            Amount::from_sat(
+                candidates
+                    .iter()
+                    .map(|(_, output)| output.value)
+                    .max()
+                    .unwrap_or_default(),
+            ),
+        ),
+    };
+
+    let payment_amount = sum_amounts(
+        tx.output
+            .iter()
+            .zip(&is_change)
+            .filter(|(output, change)| !**change && !output.script_pubkey.is_provably_unspendable())
+            .map(|(output, _)| Amount::from_sat(output.value)),
+        "outputs",
+    )?;
+    let change_amount = sum_amounts(
+        tx.output
+            .iter()
+            .zip(&is_change)
+            .filter(|(_, change)| **change)
+            .map(|(output, _)| Amount::from_sat(output.value)),
+        "outputs",
+    )?;
+
+    // The URI pays the largest output, which may not be the one the send
+    // address was taken from when it has no address of its own
+    let payment_uri = if options.emit_bip21 {
+        candidates
+            .iter()
+            .max_by_key(|(_, output)| output.value)
+            .and_then(|(_, output)| {
+                let address = script_to_address(&output.script_pubkey, network)?;
+                Some(match output.value {
+                    0 => format!("bitcoin:{}", address),
+                    value => format!(
+                        "bitcoin:{}?amount={}",
+                        address,
+                        btc_trimmed(Amount::from_sat(value))
+                    ),
+                })
+            })
+    } else {
+        None
+    };
+
+    let pay_to_info: Vec<OutputDetail> = tx
+        .output
+        .iter()
+        .zip(&psbt.outputs)
+        .zip(&is_change)
+        .enumerate()
+        .map(|(index, ((output, psbt_output), is_change))| {
+            let script = &output.script_pubkey;
+            let script_type = classify_script(script);
+            let address = script_to_address(script, network);
+            let amount = Amount::from_sat(output.value);
+            OutputDetail {
+                index,
+                amount,
+                amount_btc: btc_string(amount),
+                pay_to: address.as_ref().map(|address| address.to_string()),
+                address_encoding: address
+                    .as_ref()
+                    .map(|address| address_encoding(address).to_owned()),
+                script_type: script_type.to_owned(),
+                is_change: *is_change,
+                // The threshold follows Bitcoin Core's dust relay rules for
+                // the script type, e.g. 546 sat for P2PKH or 294 for P2WPKH
+                is_dust: output.value < script.dust_value().as_sat(),
+                spendable: !script.is_provably_unspendable(),
+                derivations: derivations(&psbt_output.bip32_derivation),
+                data: script
+                    .is_op_return()
+                    .then(|| op_return_data(script).to_hex()),
+                multisig: multisig_threshold(script),
+                script_hex: (script_type == "nonstandard").then(|| script.as_bytes().to_hex()),
+            }
+        })
+        .collect();
+
+    let mut summary = PsbtSummary {
+        txid: tx.txid().to_string(),
+        unsigned_tx_hex: serialize(tx).to_hex(),
+        network,
+        psbt_version: psbt.global.version,
+        version: tx.version,
+        locktime: tx.lock_time,
+        locktime_type: if tx.lock_time < LOCKTIME_THRESHOLD {
+            "block_height"
+        } else {
+            "timestamp"
+        }
+        .to_owned(),
+        rbf_signaled: tx
+            .input
+            .iter()
+            .any(|txin| txin.sequence < RBF_SEQUENCE_THRESHOLD),
+        // Outputs all paying back to the wallet move funds between its own
+        // addresses, and many inputs paying out to a single output empty it
+        tx_type: if !has_payment {
+            "consolidation"
+        } else if tx.output.len() == 1 && tx.input.len() > 1 {
+            "sweep"
+        } else {
+            "payment"
+        }
+        .to_owned(),
+        is_segwit: psbt
+            .inputs
+            .iter()
+            .zip(utxos)
+            .any(|(input, utxo)| spends_witness_program(input, *utxo)),
+        witness_count: psbt
+            .inputs
+            .iter()
+            .filter(|input| input.final_script_witness.is_some())
+            .count(),
+        input_count: tx.input.len(),
+        output_count: tx.output.len(),
+        send_address,
+        input_addresses,
+        inputs,
+        signing_status: signing_status(psbt).to_owned(),
+        all_inputs_resolved: resolution_errors.is_empty()
+            && !tx.input.iter().any(|txin| txin.previous_output.is_null()),
+        unresolved_input_count: resolution_errors.len(),
+        resolution_errors,
+        fee,
+        fee_btc: fee.map(btc_string),
+        fee_rate_sat_vb,
+        fee_rate_estimated,
+        fee_bucket: fee_rate_sat_vb.map(|fee_rate| {
+            options
+                .fee_buckets
+                .unwrap_or_default()
+                .classify(fee_rate)
+                .to_owned()
+        }),
+        weight,
+        vsize,
+        estimated_final_weight,
+        total_amount,
+        payment_amount,
+        change_amount,
+        total_input_amount: input_amount,
+        total_input_amount_btc: input_amount.map(btc_string),
+        total_output_amount: output_amount,
+        total_output_amount_btc: btc_string(output_amount),
+        pay_to_info,
+        global_xpubs: psbt
+            .global
+            .xpub
+            .iter()
+            .map(|(xpub, (fingerprint, path))| GlobalXpub {
+                xpub: xpub.to_string(),
+                master_fingerprint: fingerprint.to_string(),
+                derivation_path: path.to_string(),
+            })
+            .collect(),
+        unknown_fields: UnknownFields {
+            global: unknown_fields(&psbt.global.unknown, &psbt.global.proprietary),
+            inputs: psbt
+                .inputs
+                .iter()
+                .map(|input| unknown_fields(&input.unknown, &input.proprietary))
+                .collect(),
+            outputs: psbt
+                .outputs
+                .iter()
+                .map(|output| unknown_fields(&output.unknown, &output.proprietary))
+                .collect(),
+        },
+        summary_hash: String::new(),
+        finalization: None,
+        extraction: None,
+        signatures_valid: None,
+        invalid_signatures: None,
+        debug_sighash: None,
+        summary_text: None,
+        payment_uri,
+        warnings: None,
+    };
+    summary.summary_hash = summary_hash(&summary, tx);
+
+    // The summary describes the PSBT as submitted, while extraction picks up
+    // the inputs finalized here
+    let mut finalized_psbt = None;
+    if options.finalize {
+        let mut finalized = psbt.clone();
+        let unfinalized_inputs = finalize::finalize(&mut finalized);
+        summary.finalization = Some(Finalization {
+            finalized: unfinalized_inputs.is_empty(),
+            psbt: base64::encode(serialize(&finalized)),
+            unfinalized_inputs,
+        });
+        finalized_psbt = Some(finalized);
+    }
+
+    if options.extract {
+        summary.extraction = Some(extract(finalized_psbt.as_ref().unwrap_or(psbt)));
+    }
+
+    if options.verify_signatures {
+        let invalid = signatures::invalid_signatures(psbt, utxos);
+        summary.signatures_valid = Some(invalid.is_empty());
+        summary.invalid_signatures = Some(invalid);
+    }
+
+    if let Some(DebugSighashRequest { input }) = options.debug_sighash {
+        let input_data = psbt.inputs.get(input).ok_or(ParseError::InputOutOfRange {
+            input,
+            inputs: psbt.inputs.len(),
+        })?;
+        let sighash_type = input_data.sighash_type.unwrap_or(SigHashType::All);
+        let mut cache = SigHashCache::new(tx);
+        let sighash = utxos.get(input).copied().flatten().and_then(|utxo| {
+            signatures::sighash(psbt, &mut cache, input, utxo, sighash_type.as_u32())
+        });
+        summary.debug_sighash = Some(DebugSighash {
+            input,
+            algorithm: sighash.map(|(algorithm, _)| algorithm.to_owned()),
+            sighash_type: sighash_type.to_string(),
+            sighash: sighash.map(|(_, sighash)| sighash.to_hex()),
+        });
+    }
+
+    if options.explain {
+        summary.summary_text = Some(explain::explain(&summary));
+    }
+
+    // An ignored input amount is reported even without validation, as the
+    // caller expects the fee to be based on it
+    if options.validate {
+        summary.warnings = Some(validate::validate(&summary, psbt, options));
+    } else if options.input_amounts.is_some() {
+        summary.warnings = Some(validate::input_amount_mismatches(&summary, options));
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::util::bip32::{ExtendedPrivKey, ExtendedPubKey};
+
+    use super::*;
+    use crate::test_util::{
+        self, base64, key_source, multisig, op_return, p2pkh, p2wpkh, psbt, pubkey, txout,
+        DUMMY_SIG,
+    };
+
+    /// Summarizes `psbt` with `options`, which must succeed.
+    pub(crate) fn summarize(
+        psbt: &PartiallySignedTransaction,
+        options: &ParseOptions,
+    ) -> PsbtSummary {
+        parse_psbt_bytes(&serialize(psbt), options).unwrap()
+    }
+
+    /// A single P2WPKH input of 100_000 sats paying 60_000 sats to P2PKH and
+    /// 38_560 sats of change back to P2WPKH, leaving a 1_440 sat fee.
+    fn payment() -> PartiallySignedTransaction {
+        psbt(
+            &[txout(100_000, p2wpkh(1))],
+            &[txout(60_000, p2pkh(2)), txout(38_560, p2wpkh(3))],
+        )
+    }
+
+    /// Gives input `index` of `psbt` its UTXO as a whole previous
+    /// transaction instead, the way legacy inputs need it.
+    fn use_non_witness_utxo(psbt: &mut PartiallySignedTransaction, index: usize) {
+        let utxo = psbt.inputs[index].witness_utxo.take().unwrap();
+        let prev_tx = test_util::unsigned_tx(1, &[utxo]);
+        psbt.global.unsigned_tx.input[index].previous_output = OutPoint {
+            txid: prev_tx.txid(),
+            vout: 0,
+        };
+        psbt.inputs[index].non_witness_utxo = Some(prev_tx);
+    }
+
+    #[test]
+    fn signed_single_key_input_needs_no_signature() {
+        let mut psbt = psbt(&[txout(10_000, p2wpkh(1))], &[txout(9_000, p2wpkh(2))]);
+        let unsigned = summarize(&psbt, &ParseOptions::default());
+        assert!(unsigned.inputs[0].needs_signature);
+        assert_eq!(unsigned.signing_status, "unsigned");
+
+        psbt.inputs[0]
+            .partial_sigs
+            .insert(pubkey(1), DUMMY_SIG.to_vec());
+        let signed = summarize(&psbt, &ParseOptions::default());
+        assert!(!signed.inputs[0].needs_signature);
+        assert_eq!(signed.signing_status, "complete");
+    }
+
+    #[test]
+    fn two_of_three_input_needs_a_second_signature() {
+        let witness_script = multisig(2, 3);
+        let utxo = txout(10_000, witness_script.to_v0_p2wsh());
+        let mut psbt = psbt(&[utxo], &[txout(9_000, p2wpkh(4))]);
+        psbt.inputs[0].witness_script = Some(witness_script);
+
+        psbt.inputs[0]
+            .partial_sigs
+            .insert(pubkey(1), DUMMY_SIG.to_vec());
+        let one = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(one.inputs[0].multisig, Some(Multisig { m: 2, n: 3 }));
+        assert!(one.inputs[0].needs_signature);
+        assert_eq!(one.signing_status, "partially_signed");
+
+        psbt.inputs[0]
+            .partial_sigs
+            .insert(pubkey(2), DUMMY_SIG.to_vec());
+        let two = summarize(&psbt, &ParseOptions::default());
+        assert!(!two.inputs[0].needs_signature);
+        assert_eq!(two.signing_status, "complete");
+    }
+
+    #[test]
+    fn unsigned_psbt_reports_the_unsigned_txid() {
+        let psbt = payment();
+        let summary = parse_psbt(&base64(&psbt), None).unwrap();
+        assert_eq!(summary.txid, psbt.global.unsigned_tx.txid().to_string());
+        assert_eq!(summary.input_count, 1);
+        assert_eq!(summary.output_count, 2);
+    }
+
+    #[test]
+    fn fee_rate_uses_the_estimated_final_size() {
+        // 116 unsigned bytes make 464 WU, and the P2WPKH witness adds the
+        // marker and flag and 108 WU of stack, so 574 WU or 144 vB
+        let summary = parse_psbt(&base64(&payment()), None).unwrap();
+        assert_eq!(summary.fee, Some(Amount::from_sat(1_440)));
+        assert_eq!(summary.fee_rate_sat_vb, Some(10.0));
+        assert!(summary.fee_rate_estimated);
+    }
+
+    #[test]
+    fn parses_each_network_name() {
+        for (name, network) in [
+            ("bitcoin", Network::Bitcoin),
+            ("mainnet", Network::Bitcoin),
+            ("Testnet", Network::Testnet),
+            ("SIGNET", Network::Signet),
+            ("regtest", Network::Regtest),
+        ] {
+            assert_eq!(parse_network(name).unwrap(), network, "{}", name);
+        }
+        assert!(parse_network("testnet4").is_err());
+    }
+
+    #[test]
+    fn encodings_share_a_summary_hash() {
+        let psbt = serialize(&payment());
+        let base64_request = ParsePsbtRequest {
+            psbt: base64::encode(&psbt),
+            ..Default::default()
+        };
+        let hex_request = ParsePsbtRequest {
+            psbt: psbt.to_hex(),
+            encoding: Encoding::Hex,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_request(&base64_request).unwrap().summary_hash,
+            parse_request(&hex_request).unwrap().summary_hash,
+        );
+    }
+
+    #[test]
+    fn mutated_psbts_never_panic() {
+        let psbt = serialize(&payment());
+        let options = ParseOptions {
+            validate: true,
+            verify_signatures: true,
+            finalize: true,
+            explain: true,
+            ..Default::default()
+        };
+        // A fixed xorshift stream keeps failures reproducible
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..2_000 {
+            let mut bytes = psbt.clone();
+            for _ in 0..=next() % 4 {
+                let index = (next() % bytes.len() as u64) as usize;
+                match next() % 3 {
+                    0 => bytes[index] = next() as u8,
+                    1 => bytes.truncate(index),
+                    _ => bytes.insert(index, next() as u8),
+                }
+                if bytes.is_empty() {
+                    break;
+                }
+            }
+            let _ = parse_psbt(&base64::encode(&bytes), None);
+            let _ = parse_psbt_bytes(&bytes, &options);
+        }
+    }
+
+    #[test]
+    fn ignored_input_amount_is_reported_without_validation() {
+        let request: ParsePsbtRequest = serde_json::from_value(serde_json::json!({
+            "psbt": base64(&payment()),
+            "input_amounts": [50_000],
+        }))
+        .unwrap();
+        let summary = parse_request(&request).unwrap();
+        assert_eq!(summary.fee, Some(Amount::from_sat(1_440)));
+        let warnings = summary.warnings.unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "INPUT_AMOUNT_MISMATCH");
+    }
+
+    #[test]
+    fn coinbase_input_leaves_the_fee_untrusted() {
+        let mut tx = test_util::unsigned_tx(1, &[txout(50_000, p2wpkh(1))]);
+        tx.input[0].previous_output = OutPoint::null();
+        let psbt = PartiallySignedTransaction::from_unsigned_tx(tx).unwrap();
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert!(summary.inputs[0].is_coinbase);
+        assert_eq!(summary.fee, None);
+        assert!(!summary.all_inputs_resolved);
+        assert_eq!(summary.unresolved_input_count, 0);
+    }
+
+    #[test]
+    fn unresolved_input_leaves_the_fee_unknown() {
+        let mut psbt = payment();
+        psbt.inputs[0].witness_utxo = None;
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.fee, None);
+        assert!(!summary.all_inputs_resolved);
+        assert_eq!(summary.unresolved_input_count, 1);
+        assert_eq!(summary.resolution_errors[0].reason, "NO_UTXO_DATA");
+    }
+
+    #[test]
+    fn unresolved_input_leaves_the_input_total_unknown() {
+        let mut psbt = psbt(
+            &[txout(10_000, p2wpkh(1)), txout(10_000, p2wpkh(2))],
+            &[txout(15_000, p2wpkh(3))],
+        );
+        psbt.inputs[1].witness_utxo = None;
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.fee, None);
+        assert_eq!(summary.total_input_amount, None);
+        assert_eq!(summary.total_input_amount_btc, None);
+        assert_eq!(summary.total_output_amount, Amount::from_sat(15_000));
+    }
+
+    #[test]
+    fn duplicate_input_is_refused() {
+        let mut psbt = psbt(
+            &[txout(10_000, p2wpkh(1)), txout(10_000, p2wpkh(1))],
+            &[txout(19_000, p2wpkh(2))],
+        );
+        psbt.global.unsigned_tx.input[1].previous_output =
+            psbt.global.unsigned_tx.input[0].previous_output;
+
+        let error = parse_psbt_bytes(&serialize(&psbt), &ParseOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "DUPLICATE_INPUT");
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "inputs 0 and 1 both spend {}",
+                psbt.global.unsigned_tx.input[0].previous_output
+            )
+        );
+    }
+
+    /// The signed native P2WPKH example of BIP143, spending a 6.25 BTC P2PK
+    /// output and a 6 BTC P2WPKH one.
+    const BIP143_TX: &str = "01000000000102fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000494830450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed01eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac000247304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee0121025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee635711000000";
+
+    fn bip143_prevouts() -> Vec<Prevout> {
+        vec![
+            Prevout {
+                amount: Amount::from_sat(625_000_000),
+                script_pubkey:
+                    "2103c9f4836b9a4f77fc0d81f7bcb01b7f1b35916864b9476c241ce9fc198bd25432ac".into(),
+            },
+            Prevout {
+                amount: Amount::from_sat(600_000_000),
+                script_pubkey: "00141d0f172a0ecb48aee1be1f2687d2963ae33f71a1".into(),
+            },
+        ]
+    }
+
+    #[test]
+    fn raw_tx_fee_needs_its_prevouts() {
+        let bare = parse_raw_tx(BIP143_TX, Some(Network::Bitcoin), None).unwrap();
+        assert_eq!(
+            bare.txid,
+            "3335ffae0df20c5407e8de12b49405c8e912371f00fe4132bfaf95ad49c40243"
+        );
+        assert_eq!(bare.input_count, 2);
+        assert_eq!(bare.signing_status, "complete");
+        assert_eq!(bare.fee, None);
+        assert_eq!(bare.total_output_amount, Amount::from_sat(335_790_000));
+
+        let prevouts = bip143_prevouts();
+        let summary = parse_raw_tx(BIP143_TX, Some(Network::Bitcoin), Some(&prevouts)).unwrap();
+        assert_eq!(summary.txid, bare.txid);
+        assert_eq!(summary.fee, Some(Amount::from_sat(889_210_000)));
+        assert_eq!(
+            summary.inputs[1].address.as_deref(),
+            Some("bc1qr583w2swedy2acd7rung055k8t3n7udp7vyzyg")
+        );
+    }
+
+    #[test]
+    fn invalid_prevout_script_names_the_prevout() {
+        let mut prevouts = bip143_prevouts();
+        prevouts[1].script_pubkey = "00zz".into();
+
+        let error = parse_raw_tx(BIP143_TX, None, Some(&prevouts)).unwrap_err();
+        assert_eq!(error.code(), "INVALID_PREVOUT_SCRIPT");
+        assert!(matches!(
+            error,
+            ParseError::InvalidPrevoutScript { index: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn utxo_data_must_describe_the_spent_output() {
+        let mut psbt = psbt(&[txout(10_000, p2pkh(1))], &[txout(9_000, p2wpkh(2))]);
+        let error = parse_psbt_bytes(&serialize(&psbt), &ParseOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "UTXO_MISMATCH");
+        assert_eq!(
+            error.to_string(),
+            "input 0: witness UTXO given for an input that isn't segwit"
+        );
+
+        // A previous transaction with another txid than the outpoint's
+        psbt.inputs[0].witness_utxo = None;
+        psbt.inputs[0].non_witness_utxo =
+            Some(test_util::unsigned_tx(1, &[txout(10_000, p2pkh(1))]));
+        let error = parse_psbt_bytes(&serialize(&psbt), &ParseOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "UTXO_MISMATCH");
+        assert_eq!(
+            error.to_string(),
+            "input 0: non-witness UTXO is not the transaction the input spends from"
+        );
+    }
+
+    #[test]
+    fn vout_past_the_previous_outputs_is_refused() {
+        let prev_tx = test_util::unsigned_tx(1, &[txout(10_000, p2pkh(1))]);
+        let mut psbt = psbt(&[txout(10_000, p2pkh(1))], &[txout(9_000, p2wpkh(2))]);
+        psbt.global.unsigned_tx.input[0].previous_output = OutPoint {
+            txid: prev_tx.txid(),
+            vout: 1,
+        };
+        psbt.inputs[0].witness_utxo = None;
+        psbt.inputs[0].non_witness_utxo = Some(prev_tx);
+
+        let error = parse_psbt_bytes(&serialize(&psbt), &ParseOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "VOUT_OUT_OF_RANGE");
+        assert_eq!(
+            error.to_string(),
+            "input 0 spends output 1 of a transaction with 1 outputs"
+        );
+    }
+
+    #[test]
+    fn outputs_past_the_supply_are_refused() {
+        let half = MAX_MONEY / 2 + 1;
+        let psbt = psbt(
+            &[txout(10_000, p2wpkh(1))],
+            &[txout(half, p2wpkh(2)), txout(half, p2wpkh(3))],
+        );
+
+        let error = parse_psbt_bytes(&serialize(&psbt), &ParseOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "AMOUNT_OVERFLOW");
+        assert_eq!(error.to_string(), "outputs total more than 21,000,000 BTC");
+    }
+
+    #[test]
+    fn psbt_size_is_limited() {
+        let bytes = serialize(&payment());
+        assert!(decode_psbt(&bytes, Some(bytes.len())).is_ok());
+        assert!(decode_psbt(&bytes, Some(bytes.len() + 1)).is_ok());
+
+        let error = decode_psbt(&bytes, Some(bytes.len() - 1)).unwrap_err();
+        assert_eq!(error.code(), "PSBT_TOO_LARGE");
+        assert!(matches!(
+            error,
+            ParseError::PsbtTooLarge { size, max } if size == bytes.len() && max == size - 1
+        ));
+
+        let oversized = vec![0; DEFAULT_MAX_PSBT_SIZE + 1];
+        let error = decode_psbt(&oversized, None).unwrap_err();
+        assert_eq!(error.code(), "PSBT_TOO_LARGE");
+    }
+
+    #[test]
+    fn nested_multisig_is_read_from_the_witness_script() {
+        let witness_script = multisig(2, 3);
+        let redeem_script = witness_script.to_v0_p2wsh();
+        let utxo = txout(10_000, redeem_script.to_p2sh());
+        let mut psbt = psbt(&[utxo], &[txout(9_000, p2wpkh(4))]);
+        psbt.inputs[0].redeem_script = Some(redeem_script);
+
+        // The redeem script alone is just the witness program
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.inputs[0].multisig, None);
+
+        psbt.inputs[0].witness_script = Some(witness_script);
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.inputs[0].multisig, Some(Multisig { m: 2, n: 3 }));
+    }
+
+    /// The mainnet address of `script`.
+    fn address(script: &Script) -> String {
+        script_to_address(script, Network::Bitcoin)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn send_address_is_the_largest_recipient() {
+        let psbt = psbt(
+            &[txout(100_000, p2wpkh(1))],
+            &[txout(30_000, p2wpkh(2)), txout(60_000, p2wpkh(3))],
+        );
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.send_address, Some(address(&p2wpkh(3))));
+        assert_eq!(summary.total_amount, Amount::from_sat(60_000));
+    }
+
+    #[test]
+    fn send_address_skips_leading_change() {
+        let mut psbt = psbt(
+            &[txout(100_000, p2wpkh(1))],
+            &[txout(80_000, p2wpkh(5)), txout(10_000, p2wpkh(2))],
+        );
+        psbt.outputs[0]
+            .bip32_derivation
+            .insert(pubkey(5), key_source(1, "m/84'/0'/0'/1/0"));
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert!(summary.pay_to_info[0].is_change);
+        assert_eq!(summary.send_address, Some(address(&p2wpkh(2))));
+        assert_eq!(summary.total_amount, Amount::from_sat(10_000));
+    }
+
+    #[test]
+    fn op_return_output_has_no_address() {
+        let psbt = psbt(
+            &[txout(10_000, p2wpkh(1))],
+            &[txout(9_000, p2wpkh(2)), txout(0, op_return(b"hello"))],
+        );
+        let summary = summarize(&psbt, &ParseOptions::default());
+        let output = serde_json::to_value(&summary.pay_to_info[1]).unwrap();
+        assert_eq!(output["pay_to"], serde_json::Value::Null);
+        assert_eq!(output["script_type"], "op_return");
+        assert_eq!(output["data"], "68656c6c6f");
+    }
+
+    #[test]
+    fn leading_op_return_still_gets_a_summary() {
+        let psbt = psbt(
+            &[txout(10_000, p2wpkh(1))],
+            &[txout(0, op_return(b"hello")), txout(9_000, p2wpkh(2))],
+        );
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.fee, Some(Amount::from_sat(1_000)));
+        assert_eq!(summary.total_output_amount, Amount::from_sat(9_000));
+        assert_eq!(summary.output_count, 2);
+        assert_eq!(summary.pay_to_info[0].pay_to, None);
+        assert_eq!(summary.send_address, Some(address(&p2wpkh(2))));
+    }
+
+    #[test]
+    fn testnet_is_detected_from_the_coin_type() {
+        let mut psbt = payment();
+        psbt.inputs[0]
+            .bip32_derivation
+            .insert(pubkey(1), key_source(1, "m/84'/1'/0'/0/0"));
+
+        let summary = parse_psbt(&base64(&psbt), None).unwrap();
+        assert_eq!(summary.network, Network::Testnet);
+        assert!(summary.inputs[0]
+            .address
+            .as_deref()
+            .unwrap()
+            .starts_with("tb1"));
+    }
+
+    #[test]
+    fn totals_add_up_to_the_fee() {
+        let mut psbt = psbt(
+            &[
+                txout(10_000, p2wpkh(1)),
+                txout(25_000, p2pkh(1)),
+                txout(40_000, p2wpkh(2)),
+            ],
+            &[txout(50_000, p2wpkh(3)), txout(23_500, p2wpkh(4))],
+        );
+        // A P2PKH input needs the whole previous transaction
+        let prev_tx = test_util::unsigned_tx(1, &[txout(25_000, p2pkh(1))]);
+        psbt.global.unsigned_tx.input[1].previous_output = OutPoint {
+            txid: prev_tx.txid(),
+            vout: 0,
+        };
+        psbt.inputs[1].witness_utxo = None;
+        psbt.inputs[1].non_witness_utxo = Some(prev_tx);
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        let input_amount = summary.total_input_amount.unwrap();
+        assert_eq!(input_amount, Amount::from_sat(75_000));
+        assert_eq!(summary.total_output_amount, Amount::from_sat(73_500));
+        assert_eq!(
+            summary.fee,
+            Some(input_amount - summary.total_output_amount)
+        );
+        assert_eq!(
+            summary.total_input_amount_btc.as_deref(),
+            Some("0.00075000")
+        );
+    }
+
+    #[test]
+    fn locktime_below_the_threshold_is_a_block_height() {
+        let mut psbt = payment();
+        psbt.global.unsigned_tx.lock_time = 800_000;
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.locktime, 800_000);
+        assert_eq!(summary.locktime_type, "block_height");
+
+        psbt.global.unsigned_tx.lock_time = 1_700_000_000;
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.locktime_type, "timestamp");
+    }
+
+    #[test]
+    fn rbf_is_signaled_by_any_low_sequence() {
+        let mut psbt = psbt(
+            &[txout(10_000, p2wpkh(1)), txout(10_000, p2wpkh(2))],
+            &[txout(19_000, p2wpkh(3))],
+        );
+        let final_sequences = summarize(&psbt, &ParseOptions::default());
+        assert!(!final_sequences.rbf_signaled);
+        assert!(!final_sequences.inputs[0].signals_rbf);
+
+        psbt.global.unsigned_tx.input[1].sequence = 0xffff_fffd;
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert!(summary.rbf_signaled);
+        assert!(!summary.inputs[0].signals_rbf);
+        assert!(summary.inputs[1].signals_rbf);
+        assert_eq!(summary.inputs[1].sequence, 0xffff_fffd);
+    }
+
+    #[test]
+    fn change_shares_a_fingerprint_with_the_inputs() {
+        let mut psbt = payment();
+        psbt.inputs[0]
+            .bip32_derivation
+            .insert(pubkey(1), key_source(1, "m/84'/0'/0'/0/0"));
+        // Output 0 pays to a cosigner's wallet, output 1 back to ours
+        psbt.outputs[0]
+            .bip32_derivation
+            .insert(pubkey(2), key_source(2, "m/84'/0'/0'/0/0"));
+        psbt.outputs[1]
+            .bip32_derivation
+            .insert(pubkey(3), key_source(1, "m/84'/0'/0'/1/0"));
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert!(!summary.pay_to_info[0].is_change);
+        assert!(summary.pay_to_info[1].is_change);
+    }
+
+    #[test]
+    fn nested_segwit_input_reports_its_redeem_script() {
+        let redeem_script = p2wpkh(1);
+        let mut psbt = psbt(
+            &[txout(10_000, redeem_script.to_p2sh())],
+            &[txout(9_000, p2wpkh(2))],
+        );
+        psbt.inputs[0].redeem_script = Some(redeem_script.clone());
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(
+            summary.inputs[0].redeem_script_hex,
+            Some(redeem_script.as_bytes().to_hex())
+        );
+        assert!(summary.inputs[0]
+            .redeem_script_hex
+            .as_deref()
+            .unwrap()
+            .starts_with("0014"));
+        assert_eq!(summary.inputs[0].witness_script_hex, None);
+    }
+
+    #[test]
+    fn sighash_type_defaults_to_all() {
+        let mut psbt = psbt(
+            &[txout(10_000, p2wpkh(1)), txout(10_000, p2wpkh(2))],
+            &[txout(19_000, p2wpkh(3))],
+        );
+        psbt.inputs[1].sighash_type = Some(SigHashType::SinglePlusAnyoneCanPay);
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.inputs[0].sighash_type, "SIGHASH_ALL");
+        assert_eq!(
+            summary.inputs[1].sighash_type,
+            "SIGHASH_SINGLE|SIGHASH_ANYONECANPAY"
+        );
+    }
+
+    #[test]
+    fn global_xpubs_are_listed_with_their_origin() {
+        let secp = bitcoin::secp256k1::Secp256k1::new();
+        let master = ExtendedPrivKey::new_master(Network::Bitcoin, &[1; 32]).unwrap();
+        let path: DerivationPath = "m/84'/0'/0'".parse().unwrap();
+        let account = master.derive_priv(&secp, &path).unwrap();
+        let xpub = ExtendedPubKey::from_private(&secp, &account);
+        let mut psbt = payment();
+        psbt.global
+            .xpub
+            .insert(xpub, key_source(0xab, "m/84'/0'/0'"));
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.global_xpubs.len(), 1);
+        let global = &summary.global_xpubs[0];
+        assert_eq!(global.xpub, xpub.to_string());
+        assert!(global.xpub.starts_with("xpub"));
+        assert_eq!(global.master_fingerprint, "abababab");
+        assert_eq!(global.derivation_path, "m/84'/0'/0'");
+    }
+
+    #[test]
+    fn dust_follows_the_script_type() {
+        // Bitcoin Core's limits at 3 sat/vB: 294 sats for P2WPKH, 546 for P2PKH
+        let psbt = psbt(
+            &[txout(10_000, p2wpkh(1))],
+            &[
+                txout(293, p2wpkh(2)),
+                txout(300, p2wpkh(2)),
+                txout(545, p2pkh(3)),
+                txout(600, p2pkh(3)),
+            ],
+        );
+        let summary = summarize(&psbt, &ParseOptions::default());
+        let dust: Vec<bool> = summary
+            .pay_to_info
+            .iter()
+            .map(|output| output.is_dust)
+            .collect();
+        assert_eq!(dust, [true, false, true, false]);
+    }
+
+    #[test]
+    fn version_2_psbt_is_refused_by_name() {
+        let mut bytes = b"psbt\xff".to_vec();
+        // PSBT_GLOBAL_TX_VERSION, then a 256 byte proprietary key whose
+        // length takes a 3 byte compact size, then PSBT_GLOBAL_VERSION
+        bytes.extend_from_slice(&[0x01, 0x02, 0x04, 0x02, 0x00, 0x00, 0x00]);
+        bytes.extend_from_slice(&[0xfd, 0x00, 0x01, 0xfc]);
+        bytes.extend_from_slice(&[0x00; 255]);
+        bytes.push(0x00);
+        bytes.extend_from_slice(&[0x01, 0xfb, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(declared_psbt_version(&bytes), Some(2));
+
+        let error = parse_psbt_bytes(&bytes, &ParseOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "UNSUPPORTED_PSBT_VERSION");
+        assert_eq!(
+            error.to_string(),
+            "PSBT version 2 is not supported, only version 0 is"
+        );
+        assert_eq!(declared_psbt_version(&serialize(&payment())), None);
+    }
+
+    #[test]
+    fn proprietary_global_key_is_listed_as_unknown() {
+        let mut psbt = payment();
+        let key = ProprietaryKey {
+            prefix: b"acme".to_vec(),
+            subtype: 1,
+            key: vec![0xaa],
+        };
+        psbt.global.proprietary.insert(key, vec![0xde, 0xad]);
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        let global = &summary.unknown_fields.global;
+        assert_eq!(global.len(), 1);
+        // PSBT_GLOBAL_PROPRIETARY, the prefix with its length, the subtype
+        // and the key data
+        assert_eq!(global[0].key, "fc0461636d6501aa");
+        assert_eq!(global[0].value, "dead");
+        assert!(summary.unknown_fields.inputs[0].is_empty());
+    }
+
+    #[test]
+    fn btc_amounts_keep_eight_decimals() {
+        assert_eq!(btc_string(Amount::from_sat(100_000_000)), "1.00000000");
+        assert_eq!(btc_string(Amount::from_sat(1)), "0.00000001");
+        assert_eq!(btc_trimmed(Amount::from_sat(1_000_000)), "0.01");
+    }
+
+    #[test]
+    fn extracts_only_a_finalized_transaction() {
+        let options = ParseOptions {
+            finalize: true,
+            extract: true,
+            ..Default::default()
+        };
+        let mut psbt = payment();
+        let unsigned = summarize(&psbt, &options).extraction.unwrap();
+        assert_eq!(unsigned.final_tx_hex, None);
+        let note = unsigned.note.unwrap();
+        assert_eq!(note.code, "NOT_FINALIZED");
+        assert_eq!(note.message, "inputs not yet finalized: 0");
+
+        test_util::sign(&mut psbt, 0, 1);
+        let extraction = summarize(&psbt, &options).extraction.unwrap();
+        assert!(extraction.note.is_none());
+        let tx: Transaction =
+            deserialize(&Vec::from_hex(&extraction.final_tx_hex.unwrap()).unwrap()).unwrap();
+        assert_eq!(extraction.txid, Some(tx.txid().to_string()));
+        assert_eq!(tx.txid(), psbt.global.unsigned_tx.txid());
+        assert_eq!(tx.input[0].witness.len(), 2);
+    }
+
+    #[test]
+    fn derivations_are_listed_on_inputs_and_outputs() {
+        let mut psbt = payment();
+        psbt.inputs[0]
+            .bip32_derivation
+            .insert(pubkey(1), key_source(0x12, "m/84'/0'/0'/0/5"));
+        psbt.outputs[1]
+            .bip32_derivation
+            .insert(pubkey(3), key_source(0x12, "m/84'/0'/0'/1/2"));
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        let input = &summary.inputs[0].derivations;
+        assert_eq!(input.len(), 1);
+        assert_eq!(input[0].pubkey, pubkey(1).to_string());
+        assert_eq!(input[0].master_fingerprint, "12121212");
+        assert_eq!(input[0].derivation_path, "m/84'/0'/0'/0/5");
+
+        assert!(summary.pay_to_info[0].derivations.is_empty());
+        let output = &summary.pay_to_info[1].derivations;
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].pubkey, pubkey(3).to_string());
+        assert_eq!(output[0].derivation_path, "m/84'/0'/0'/1/2");
+    }
+
+    #[test]
+    fn transaction_without_outputs_is_refused() {
+        let psbt = psbt(&[txout(10_000, p2wpkh(1))], &[]);
+        let error = parse_psbt_bytes(&serialize(&psbt), &ParseOptions::default()).unwrap_err();
+        assert!(matches!(error, ParseError::NoOutputs));
+        assert_eq!(error.code(), "NO_OUTPUTS");
+    }
+
+    #[test]
+    fn segwit_is_judged_by_the_spent_scripts() {
+        let mut legacy = psbt(&[txout(10_000, p2pkh(1))], &[txout(9_000, p2wpkh(2))]);
+        use_non_witness_utxo(&mut legacy, 0);
+        assert!(!summarize(&legacy, &ParseOptions::default()).is_segwit);
+
+        let segwit = psbt(&[txout(10_000, p2wpkh(1))], &[txout(9_000, p2pkh(2))]);
+        assert!(summarize(&segwit, &ParseOptions::default()).is_segwit);
+    }
+
+    #[test]
+    fn url_safe_base64_parses_like_standard() {
+        let psbt = psbt(
+            &[txout(10_000, p2wpkh(1)), txout(10_000, p2wpkh(1))],
+            &[txout(19_000, p2wpkh(2))],
+        );
+        let standard = base64(&psbt);
+        assert!(standard.contains('+') || standard.contains('/'));
+        let url_safe = base64::encode_config(serialize(&psbt), base64::URL_SAFE);
+        assert_ne!(standard, url_safe);
+
+        let from_standard = parse_psbt(&standard, None).unwrap();
+        let from_url_safe = parse_psbt(&url_safe, None).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_standard).unwrap(),
+            serde_json::to_value(&from_url_safe).unwrap()
+        );
+    }
+
+    #[test]
+    fn tx_type_tells_consolidations_from_payments() {
+        let mut psbt = psbt(
+            &[txout(10_000, p2wpkh(1)), txout(10_000, p2wpkh(2))],
+            &[txout(19_000, p2wpkh(3))],
+        );
+        assert_eq!(summarize(&psbt, &ParseOptions::default()).tx_type, "sweep");
+
+        psbt.outputs[0]
+            .bip32_derivation
+            .insert(pubkey(3), key_source(1, "m/84'/0'/0'/1/0"));
+        assert_eq!(
+            summarize(&psbt, &ParseOptions::default()).tx_type,
+            "consolidation"
+        );
+
+        assert_eq!(
+            summarize(&payment(), &ParseOptions::default()).tx_type,
+            "payment"
+        );
+    }
+
+    #[test]
+    fn signers_are_the_keys_that_signed() {
+        let witness_script = multisig(2, 3);
+        let mut psbt = psbt(
+            &[txout(10_000, witness_script.to_v0_p2wsh())],
+            &[txout(9_000, p2wpkh(4))],
+        );
+        psbt.inputs[0].witness_script = Some(witness_script);
+        assert!(summarize(&psbt, &ParseOptions::default()).inputs[0]
+            .signers
+            .is_empty());
+
+        test_util::sign(&mut psbt, 0, 3);
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.inputs[0].signers, [pubkey(3).to_string()]);
+        assert_eq!(summary.inputs[0].partial_sig_count, 1);
+    }
+
+    #[test]
+    fn outputs_keep_their_index_around_op_return() {
+        let psbt = psbt(
+            &[txout(20_000, p2wpkh(1))],
+            &[
+                txout(9_000, p2wpkh(2)),
+                txout(0, op_return(b"hello")),
+                txout(9_000, p2wpkh(3)),
+            ],
+        );
+        let summary = summarize(&psbt, &ParseOptions::default());
+        let outputs: Vec<(usize, bool)> = summary
+            .pay_to_info
+            .iter()
+            .map(|output| (output.index, output.spendable))
+            .collect();
+        assert_eq!(outputs, [(0, true), (1, false), (2, true)]);
+    }
+
+    #[test]
+    fn legacy_and_segwit_inputs_resolve_side_by_side() {
+        let mut psbt = psbt(
+            &[txout(30_000, p2pkh(1)), txout(20_000, p2wpkh(2))],
+            &[txout(49_000, p2wpkh(3))],
+        );
+        use_non_witness_utxo(&mut psbt, 0);
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert!(summary.all_inputs_resolved);
+        let inputs: Vec<_> = summary
+            .inputs
+            .iter()
+            .map(|input| {
+                (
+                    input.has_non_witness_utxo,
+                    input.has_witness_utxo,
+                    input.amount,
+                )
+            })
+            .collect();
+        assert_eq!(
+            inputs,
+            [
+                (true, false, Some(Amount::from_sat(30_000))),
+                (false, true, Some(Amount::from_sat(20_000))),
+            ]
+        );
+        assert_eq!(summary.fee, Some(Amount::from_sat(1_000)));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_parse_matches_the_sync_one() {
+        let request = ParsePsbtRequest {
+            psbt: base64(&payment()),
+            ..Default::default()
+        };
+        let sync = parse_request(&request).unwrap();
+        let async_summary = parse_psbt_async(request).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&sync).unwrap(),
+            serde_json::to_value(&async_summary).unwrap()
+        );
+    }
+
+    #[test]
+    fn unresolved_input_still_counts() {
+        let mut psbt = psbt(
+            &[txout(10_000, p2wpkh(1)), txout(10_000, p2wpkh(2))],
+            &[txout(15_000, p2wpkh(3))],
+        );
+        psbt.inputs[0].witness_utxo = None;
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.input_count, 2);
+        assert_eq!(summary.inputs.len(), 2);
+        assert_eq!(summary.inputs[0].amount, None);
+        assert_eq!(summary.unresolved_input_count, 1);
+        assert_eq!(summary.resolution_errors[0].input_index, 0);
+    }
+
+    #[test]
+    fn fee_buckets_start_at_their_threshold() {
+        let buckets = FeeBuckets::default();
+        for (fee_rate, bucket) in [
+            (0.0, "low"),
+            (4.99, "low"),
+            (5.0, "medium"),
+            (29.99, "medium"),
+            (30.0, "high"),
+            (199.99, "high"),
+            (200.0, "extreme"),
+        ] {
+            assert_eq!(buckets.classify(fee_rate), bucket, "{}", fee_rate);
+        }
+    }
+
+    #[test]
+    fn network_deserializes_from_name_id_or_magic() {
+        let network = |value: serde_json::Value| {
+            serde_json::from_value::<ParseOptions>(serde_json::json!({ "network": value }))
+                .map(|options| options.network)
+        };
+        let testnet = Some(Network::Testnet);
+        assert_eq!(network("testnet".into()).unwrap(), testnet);
+        assert_eq!(network(1.into()).unwrap(), testnet);
+        assert_eq!(network(Network::Testnet.magic().into()).unwrap(), testnet);
+        assert_eq!(network(serde_json::Value::Null).unwrap(), None);
+
+        let error = network(7.into()).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("unknown network number 7, expected 0 to 3 or a network magic"));
+    }
+
+    #[test]
+    fn minimal_summary_omits_the_arrays() {
+        let request = ParsePsbtRequest {
+            psbt: base64(&payment()),
+            ..Default::default()
+        };
+        let full = parse_request(&request).unwrap();
+        let minimal = serde_json::to_value(parse_request_minimal(&request).unwrap()).unwrap();
+        for array in ["inputs", "pay_to_info", "input_addresses", "warnings"] {
+            assert!(minimal.get(array).is_none(), "{}", array);
+        }
+        assert_eq!(minimal["txid"], full.txid);
+        assert_eq!(minimal["fee"], 1_440);
+        assert_eq!(minimal["fee_rate_sat_vb"], 10.0);
+        assert_eq!(minimal["total_output_amount"], 98_560);
+        assert_eq!(minimal["output_count"], 2);
+    }
+
+    #[test]
+    fn input_reports_the_script_it_spends() {
+        let mut psbt = psbt(
+            &[txout(10_000, p2wpkh(1)), txout(10_000, p2wpkh(2))],
+            &[txout(19_000, p2wpkh(3))],
+        );
+        psbt.inputs[1].witness_utxo = None;
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(
+            summary.inputs[0].prev_script_pubkey_hex,
+            Some(p2wpkh(1).as_bytes().to_hex())
+        );
+        assert_eq!(summary.inputs[1].prev_script_pubkey_hex, None);
+    }
+
+    #[test]
+    fn whitespace_in_base64_is_ignored() {
+        let clean = base64(&payment());
+        let wrapped: String = clean
+            .as_bytes()
+            .chunks(64)
+            .map(|line| std::str::from_utf8(line).unwrap())
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        let padded = format!("  {}\n", wrapped);
+
+        let expected = serde_json::to_value(parse_psbt(&clean, None).unwrap()).unwrap();
+        let summary = parse_psbt(&padded, None).unwrap();
+        assert_eq!(serde_json::to_value(summary).unwrap(), expected);
+    }
+
+    #[test]
+    fn change_is_split_from_the_payment() {
+        let mut psbt = payment();
+        psbt.outputs[1]
+            .bip32_derivation
+            .insert(pubkey(3), key_source(1, "m/84'/0'/0'/1/0"));
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert_eq!(summary.payment_amount, Amount::from_sat(60_000));
+        assert_eq!(summary.change_amount, Amount::from_sat(38_560));
+        assert_eq!(
+            summary.payment_amount + summary.change_amount,
+            summary.total_output_amount
+        );
+    }
+
+    #[test]
+    fn given_input_amounts_fill_in_the_fee() {
+        let mut psbt = payment();
+        psbt.inputs[0].witness_utxo = None;
+        assert_eq!(summarize(&psbt, &ParseOptions::default()).fee, None);
+
+        let options = ParseOptions {
+            input_amounts: Some(vec![Some(Amount::from_sat(100_000))]),
+            ..Default::default()
+        };
+        let summary = summarize(&psbt, &options);
+        assert_eq!(summary.fee, Some(Amount::from_sat(1_440)));
+        assert_eq!(summary.total_input_amount, Some(Amount::from_sat(100_000)));
+        assert_eq!(summary.inputs[0].amount, Some(Amount::from_sat(100_000)));
+    }
+
+    #[test]
+    fn relative_locktime_counts_blocks_or_time() {
+        let mut psbt = psbt(
+            &[
+                txout(10_000, p2wpkh(1)),
+                txout(10_000, p2wpkh(1)),
+                txout(10_000, p2wpkh(1)),
+            ],
+            &[txout(29_000, p2wpkh(2))],
+        );
+        let sequences = [144, SEQUENCE_LOCKTIME_TYPE_FLAG | 10, 0xffff_ffff];
+        for (txin, sequence) in psbt.global.unsigned_tx.input.iter_mut().zip(sequences) {
+            txin.sequence = sequence;
+        }
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        let locktimes: Vec<_> = summary
+            .inputs
+            .iter()
+            .map(|input| {
+                input.relative_locktime.as_ref().map(|locktime| {
+                    (
+                        locktime.lock_type.as_str(),
+                        locktime.value,
+                        locktime.seconds,
+                    )
+                })
+            })
+            .collect();
+        assert_eq!(
+            locktimes,
+            [
+                Some(("blocks", 144, None)),
+                Some(("time", 10, Some(5_120))),
+                None,
+            ]
+        );
+
+        // Version 1 transactions don't enforce relative locktimes
+        psbt.global.unsigned_tx.version = 1;
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert!(summary
+            .inputs
+            .iter()
+            .all(|input| input.relative_locktime.is_none()));
+    }
+
+    #[test]
+    fn taproot_output_renders_as_bech32m() {
+        let p2tr = bitcoin::blockdata::script::Builder::new()
+            .push_int(1)
+            .push_slice(&[7; 32])
+            .into_script();
+        let psbt = psbt(
+            &[txout(10_000, p2wpkh(1))],
+            &[txout(4_000, p2tr), txout(5_000, p2wpkh(2))],
+        );
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        let taproot = &summary.pay_to_info[0];
+        assert!(taproot.pay_to.as_deref().unwrap().starts_with("bc1p"));
+        assert_eq!(taproot.address_encoding.as_deref(), Some("bech32m"));
+        assert_eq!(taproot.script_type, "p2tr");
+        assert_eq!(
+            summary.pay_to_info[1].address_encoding.as_deref(),
+            Some("bech32")
+        );
+    }
+
+    #[test]
+    fn raw_transaction_is_pointed_to_rawtx() {
+        let tx = serialize(&test_util::unsigned_tx(1, &[txout(9_000, p2wpkh(1))]));
+        let error = parse_psbt(&base64::encode(&tx), None).unwrap_err();
+        assert!(matches!(error, ParseError::LooksLikeRawTx));
+        assert_eq!(error.code(), "NOT_A_PSBT_LOOKS_LIKE_RAW_TX");
+    }
+
+    #[test]
+    fn unsigned_tx_hex_has_the_reported_txid() {
+        let summary = summarize(&payment(), &ParseOptions::default());
+        let tx: Transaction =
+            deserialize(&Vec::from_hex(&summary.unsigned_tx_hex).unwrap()).unwrap();
+        assert_eq!(tx.txid().to_string(), summary.txid);
+        assert_eq!(tx.output.len(), 2);
+    }
+
+    #[test]
+    fn resolution_errors_name_only_the_unresolved_inputs() {
+        let mut psbt = psbt(
+            &[
+                txout(10_000, p2wpkh(1)),
+                txout(10_000, p2wpkh(2)),
+                txout(10_000, p2wpkh(3)),
+            ],
+            &[txout(25_000, p2wpkh(4))],
+        );
+        psbt.inputs[0].witness_utxo = None;
+        psbt.inputs[2].witness_utxo = None;
+
+        let summary = summarize(&psbt, &ParseOptions::default());
+        assert!(!summary.all_inputs_resolved);
+        assert_eq!(summary.unresolved_input_count, 2);
+        let errors: Vec<_> = summary
+            .resolution_errors
+            .iter()
+            .map(|error| (error.input_index, error.reason.as_str()))
+            .collect();
+        assert_eq!(errors, [(0, "NO_UTXO_DATA"), (2, "NO_UTXO_DATA")]);
+        assert_eq!(summary.inputs[1].amount, Some(Amount::from_sat(10_000)));
+    }
+}
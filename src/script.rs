@@ -0,0 +1,178 @@
+use bitcoin::blockdata::opcodes;
+use bitcoin::blockdata::script::{Instruction, Script};
+use bitcoin::util::address::Payload;
+use bitcoin::{Address, Network};
+
+use crate::Multisig;
+
+/// Renders the address `script` pays to on `network`, `None` for scripts
+/// with no standard address.
+///
+/// Segwit v0 programs only have an address at the 20 and 32 byte lengths
+/// BIP141 defines, which `Address::from_script` doesn't check.
+pub fn script_to_address(script: &Script, network: Network) -> Option<Address> {
+    if script.is_witness_program()
+        && script.as_bytes()[0] == opcodes::all::OP_PUSHBYTES_0.into_u8()
+        && !script.is_v0_p2wpkh()
+        && !script.is_v0_p2wsh()
+    {
+        return None;
+    }
+    Address::from_script(script, network)
+}
+
+/// Names the encoding `address` is rendered in: `base58` for legacy
+/// addresses, `bech32` for segwit v0 programs and `bech32m` for later
+/// versions, as BIP350 requires.
+pub fn address_encoding(address: &Address) -> &'static str {
+    match &address.payload {
+        Payload::PubkeyHash(_) | Payload::ScriptHash(_) => "base58",
+        Payload::WitnessProgram { version, .. } if version.to_u8() == 0 => "bech32",
+        Payload::WitnessProgram { .. } => "bech32m",
+    }
+}
+
+/// Labels the standard template `script` follows.
+pub fn classify_script(script: &Script) -> &'static str {
+    if script.is_p2pkh() {
+        "p2pkh"
+    } else if script.is_p2sh() {
+        "p2sh"
+    } else if script.is_v0_p2wpkh() {
+        "p2wpkh"
+    } else if script.is_v0_p2wsh() {
+        "p2wsh"
+    } else if is_v1_p2tr(script) {
+        "p2tr"
+    } else if script.is_op_return() {
+        "op_return"
+    } else if multisig_template(script).is_some() {
+        "bare_multisig"
+    } else {
+        "nonstandard"
+    }
+}
+
+/// Checks for a segwit v1 program with a 32 byte key, which this version of
+/// `bitcoin` has no predicate for.
+pub(crate) fn is_v1_p2tr(script: &Script) -> bool {
+    let bytes = script.as_bytes();
+    script.is_witness_program()
+        && bytes[0] == opcodes::all::OP_PUSHNUM_1.into_u8()
+        && bytes.len() == 34
+}
+
+/// Concatenates the data pushes following the `OP_RETURN` of `script`.
+pub(crate) fn op_return_data(script: &Script) -> Vec<u8> {
+    script
+        .instructions()
+        .skip(1)
+        .filter_map(|instruction| match instruction {
+            Ok(Instruction::PushBytes(data)) => Some(data),
+            _ => None,
+        })
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// Reads the value pushed by an `OP_1` through `OP_16` instruction.
+fn small_int(instruction: &Instruction) -> Option<usize> {
+    match instruction {
+        Instruction::Op(op) => {
+            let op = op.into_u8();
+            let first = opcodes::all::OP_PUSHNUM_1.into_u8();
+            let last = opcodes::all::OP_PUSHNUM_16.into_u8();
+            (first..=last)
+                .contains(&op)
+                .then(|| (op - first + 1) as usize)
+        }
+        _ => None,
+    }
+}
+
+/// Recognizes the `OP_m <pubkey>... OP_n OP_CHECKMULTISIG` template.
+pub(crate) fn multisig_threshold(script: &Script) -> Option<Multisig> {
+    multisig_template(script).map(|(multisig, _)| multisig)
+}
+
+/// Recognizes the multisig template, returning its threshold and its keys in
+/// script order.
+pub(crate) fn multisig_template(script: &Script) -> Option<(Multisig, Vec<&[u8]>)> {
+    let instructions: Vec<Instruction> = script.instructions().collect::<Result<_, _>>().ok()?;
+    let (last, rest) = instructions.split_last()?;
+    if *last != Instruction::Op(opcodes::all::OP_CHECKMULTISIG) {
+        return None;
+    }
+    let (first, rest) = rest.split_first()?;
+    let (n, keys) = rest.split_last()?;
+    let m = small_int(first)?;
+    let n = small_int(n)?;
+
+    let keys: Vec<&[u8]> = keys
+        .iter()
+        .map(|key| match key {
+            Instruction::PushBytes(key) if key.len() == 33 || key.len() == 65 => Some(*key),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    (keys.len() == n && m <= n).then_some((Multisig { m, n }, keys))
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::blockdata::script::Builder;
+
+    use super::*;
+    use crate::test_util::{multisig, p2pkh, p2wpkh};
+
+    #[test]
+    fn classifies_each_template() {
+        let p2tr = Builder::new()
+            .push_int(1)
+            .push_slice(&[7; 32])
+            .into_script();
+        let op_return = Builder::new()
+            .push_opcode(opcodes::all::OP_RETURN)
+            .push_slice(b"hello")
+            .into_script();
+        let nonstandard = Builder::new()
+            .push_opcode(opcodes::all::OP_PUSHNUM_1)
+            .into_script();
+
+        for (script, label) in [
+            (p2pkh(1), "p2pkh"),
+            (multisig(1, 2).to_p2sh(), "p2sh"),
+            (p2wpkh(1), "p2wpkh"),
+            (multisig(1, 2).to_v0_p2wsh(), "p2wsh"),
+            (p2tr, "p2tr"),
+            (op_return, "op_return"),
+            (multisig(1, 2), "bare_multisig"),
+            (nonstandard, "nonstandard"),
+        ] {
+            assert_eq!(classify_script(&script), label, "{}", script);
+        }
+    }
+
+    #[test]
+    fn renders_only_standard_scripts_as_addresses() {
+        let address = script_to_address(&p2wpkh(1), Network::Bitcoin).unwrap();
+        assert_eq!(address.script_pubkey(), p2wpkh(1));
+        assert!(address.to_string().starts_with("bc1q"));
+        let address = script_to_address(&p2pkh(1), Network::Testnet).unwrap();
+        assert_eq!(address.script_pubkey(), p2pkh(1));
+
+        let nonstandard = Builder::new()
+            .push_opcode(opcodes::all::OP_PUSHNUM_1)
+            .into_script();
+        assert_eq!(script_to_address(&nonstandard, Network::Bitcoin), None);
+
+        // A v0 program of neither 20 nor 32 bytes
+        let invalid_v0 = Builder::new()
+            .push_int(0)
+            .push_slice(&[7; 24])
+            .into_script();
+        assert!(invalid_v0.is_witness_program());
+        assert_eq!(script_to_address(&invalid_v0, Network::Bitcoin), None);
+    }
+}
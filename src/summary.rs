@@ -0,0 +1,534 @@
+use std::borrow::Cow;
+
+use bitcoin::{Amount, Network};
+use serde::{Deserialize, Serialize};
+
+use crate::parse_network;
+
+/// Serializes an amount as its number of satoshis, leaving the BTC string to
+/// a sibling field.
+pub(crate) fn serialize_sat<S>(amount: &Amount, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_u64(amount.as_sat())
+}
+
+/// Serializes an optional amount as its number of satoshis.
+pub(crate) fn serialize_optional_sat<S>(
+    amount: &Option<Amount>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match amount {
+        Some(amount) => serializer.serialize_some(&amount.as_sat()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserializes an amount from its number of satoshis.
+pub(crate) fn deserialize_sat<'de, D>(deserializer: D) -> Result<Amount, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(Amount::from_sat)
+}
+
+/// Deserializes an optional amount from its number of satoshis.
+pub(crate) fn deserialize_optional_sat<'de, D>(deserializer: D) -> Result<Option<Amount>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<u64>::deserialize(deserializer).map(|sat| sat.map(Amount::from_sat))
+}
+
+fn serialize_network_name<S>(network: &Network, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.collect_str(network)
+}
+
+fn deserialize_network_name<'de, D>(deserializer: D) -> Result<Network, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    parse_network(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+/// A summary of a PSBT's unsigned transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PsbtSummary {
+    pub txid: String,
+    /// The serialized unsigned transaction the signers sign, as hex, which
+    /// hashes to the txid.
+    pub unsigned_tx_hex: String,
+    #[serde(
+        serialize_with = "serialize_network_name",
+        deserialize_with = "deserialize_network_name"
+    )]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub network: Network,
+    pub psbt_version: u32,
+    pub version: i32,
+    pub locktime: u32,
+    pub locktime_type: String,
+    /// Whether any input signals BIP125 replaceability, so the transaction
+    /// can be fee bumped.
+    pub rbf_signaled: bool,
+    /// `consolidation` when every output is change, `sweep` when several
+    /// inputs pay a single output, `payment` otherwise.
+    pub tx_type: String,
+    /// Whether any input spends a witness program, natively or nested in
+    /// P2SH, or carries a final witness.
+    pub is_segwit: bool,
+    /// The number of inputs carrying a final witness.
+    pub witness_count: usize,
+    /// The number of inputs the transaction spends, whether or not their
+    /// UTXOs could be resolved.
+    pub input_count: usize,
+    pub output_count: usize,
+    /// The address of the largest payment, null when no output paid to has
+    /// a standard address.
+    pub send_address: Option<String>,
+    pub input_addresses: Vec<String>,
+    pub inputs: Vec<InputDetail>,
+    /// `unsigned` while no input is signed, `complete` once every input is
+    /// finalized or has enough signatures to be, `partially_signed` otherwise.
+    pub signing_status: String,
+    /// Whether the UTXO of every input is known, so the fee can be trusted.
+    /// A coinbase input spends no UTXO and leaves the fee unknown, so it
+    /// makes this false without counting as unresolved.
+    pub all_inputs_resolved: bool,
+    /// The number of inputs whose UTXO couldn't be resolved.
+    pub unresolved_input_count: usize,
+    /// The inputs whose UTXO couldn't be resolved, and so are missing from
+    /// `input_addresses`.
+    pub resolution_errors: Vec<ResolutionError>,
+    /// The fee, unknown while any input's UTXO is unresolved and no input
+    /// amount was given in its place.
+    #[serde(
+        serialize_with = "serialize_optional_sat",
+        deserialize_with = "deserialize_optional_sat"
+    )]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<u64>"))]
+    pub fee: Option<Amount>,
+    /// The fee in BTC, with 8 fractional digits.
+    pub fee_btc: Option<String>,
+    /// The fee over the virtual size of the finalized transaction, estimated
+    /// while inputs are unsigned.
+    pub fee_rate_sat_vb: Option<f64>,
+    /// Whether the fee rate is over the estimated finalized size, false when
+    /// that can't be estimated and the unsigned size is used instead.
+    pub fee_rate_estimated: bool,
+    /// `low`, `medium`, `high` or `extreme`, a rough reading of the fee rate.
+    pub fee_bucket: Option<String>,
+    /// Weight of the unsigned transaction, which has empty scriptSigs and no
+    /// witnesses.
+    pub weight: usize,
+    /// Virtual size of the unsigned transaction, the weight divided by four
+    /// and rounded up.
+    pub vsize: usize,
+    /// Estimated weight once every input is finalized, when all of their
+    /// scripts are known.
+    pub estimated_final_weight: Option<usize>,
+    /// The value of the output `send_address` was taken from, or of the
+    /// largest payment when it's null.
+    #[serde(serialize_with = "serialize_sat", deserialize_with = "deserialize_sat")]
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub total_amount: Amount,
+    /// The value of the spendable outputs paying someone else.
+    #[serde(serialize_with = "serialize_sat", deserialize_with = "deserialize_sat")]
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub payment_amount: Amount,
+    /// The value of the outputs paying back to the signer's wallet.
+    #[serde(serialize_with = "serialize_sat", deserialize_with = "deserialize_sat")]
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub change_amount: Amount,
+    /// The value of the inputs, null along with the fee while any input's
+    /// amount is unknown.
+    #[serde(
+        serialize_with = "serialize_optional_sat",
+        deserialize_with = "deserialize_optional_sat"
+    )]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<u64>"))]
+    pub total_input_amount: Option<Amount>,
+    pub total_input_amount_btc: Option<String>,
+    #[serde(serialize_with = "serialize_sat", deserialize_with = "deserialize_sat")]
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub total_output_amount: Amount,
+    pub total_output_amount_btc: String,
+    pub pay_to_info: Vec<OutputDetail>,
+    pub global_xpubs: Vec<GlobalXpub>,
+    /// Unknown and proprietary fields, kept so custom metadata can be checked.
+    pub unknown_fields: UnknownFields,
+    /// A SHA256 over the txid, inputs, outputs and fee, as hex, equal for
+    /// PSBTs summarizing the same transaction.
+    pub summary_hash: String,
+    /// The finalized PSBT, present only when finalization was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finalization: Option<Finalization>,
+    /// The broadcastable transaction, present only when extraction was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extraction: Option<Extraction>,
+    /// Whether every partial signature verifies, present only when signature
+    /// verification was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signatures_valid: Option<bool>,
+    /// The partial signatures that failed verification.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invalid_signatures: Option<Vec<InvalidSignature>>,
+    /// The sighash of one input, present only when it was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debug_sighash: Option<DebugSighash>,
+    /// The transaction described in a sentence, e.g. "Sending 0.01 BTC to
+    /// bc1q... with a 0.0001 BTC fee (12 sat/vB), plus 0.005 BTC change.",
+    /// present only when an explanation was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary_text: Option<String>,
+    /// A BIP21 URI paying the largest output's amount to its address, e.g.
+    /// `bitcoin:bc1q...?amount=0.01`, present only when requested and that
+    /// output has a standard address.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_uri: Option<String>,
+    /// Findings of the sanity checks, present only when validation was requested,
+    /// or just the `INPUT_AMOUNT_MISMATCH` ones when input amounts were given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<Vec<Warning>>,
+}
+
+/// An input of the transaction and the UTXO it spends, when known.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InputDetail {
+    /// The position of the input in the transaction.
+    pub index: usize,
+    pub prev_txid: String,
+    pub vout: u32,
+    pub sequence: u32,
+    /// Whether the sequence number signals BIP125 replaceability.
+    pub signals_rbf: bool,
+    /// Whether the input spends the null outpoint, as a coinbase input does,
+    /// so there's no UTXO to resolve.
+    pub is_coinbase: bool,
+    /// The BIP68 relative locktime the sequence number enforces, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relative_locktime: Option<RelativeLocktime>,
+    pub address: Option<String>,
+    #[serde(
+        serialize_with = "serialize_optional_sat",
+        deserialize_with = "deserialize_optional_sat"
+    )]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<u64>"))]
+    pub amount: Option<Amount>,
+    /// The amount in BTC, with 8 fractional digits.
+    pub amount_btc: Option<String>,
+    /// The script of the UTXO spent, as hex.
+    pub prev_script_pubkey_hex: Option<String>,
+    pub script_type: Option<String>,
+    pub has_witness_utxo: bool,
+    pub has_non_witness_utxo: bool,
+    /// The declared sighash type, `SIGHASH_ALL` when unset.
+    pub sighash_type: String,
+    pub partial_sig_count: usize,
+    /// The public keys that have provided a partial signature, as hex.
+    pub signers: Vec<String>,
+    /// The threshold of a multisig witness or redeem script.
+    pub multisig: Option<Multisig>,
+    pub redeem_script_hex: Option<String>,
+    pub witness_script_hex: Option<String>,
+    /// The BIP32 origins of the keys involved in spending the input.
+    pub derivations: Vec<Derivation>,
+    /// Whether the final scriptSig or witness has been assembled.
+    pub finalized: bool,
+    /// Whether the input still has to be signed before it can be finalized.
+    pub needs_signature: bool,
+    /// How a Taproot output is spent, present only for Taproot inputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taproot: Option<TaprootSpend>,
+}
+
+/// An input whose UTXO couldn't be resolved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ResolutionError {
+    pub input_index: usize,
+    /// `NO_UTXO_DATA` when the input carries no UTXO. An input spending an
+    /// output its previous transaction lacks fails with `VOUT_OUT_OF_RANGE`
+    /// instead.
+    pub reason: String,
+}
+
+/// A BIP68 relative locktime, which holds an input back until the output it
+/// spends is old enough.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RelativeLocktime {
+    /// `blocks` or `time`.
+    #[serde(rename = "type")]
+    pub lock_type: String,
+    /// The number of blocks, or of 512 second intervals.
+    pub value: u16,
+    /// The time the locktime stands for, present only for time based ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seconds: Option<u32>,
+}
+
+/// The Taproot spend of an input, from its BIP371 fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TaprootSpend {
+    /// `key` or `script`, unknown until the input is signed.
+    pub spend_path: Option<String>,
+    /// The x-only internal key, as hex.
+    pub internal_key: Option<String>,
+    /// The root of the script tree, as hex.
+    pub merkle_root: Option<String>,
+}
+
+/// The PSBT with every input finalized that could be.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Finalization {
+    /// Whether every input is now finalized.
+    pub finalized: bool,
+    /// The finalized PSBT, as base64.
+    pub psbt: String,
+    /// The inputs lacking signatures or scripts to be finalized.
+    pub unfinalized_inputs: Vec<usize>,
+}
+
+/// The finalized transaction extracted from a complete PSBT.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Extraction {
+    /// The serialized transaction, `None` until every input is finalized.
+    pub final_tx_hex: Option<String>,
+    /// The txid of the finalized transaction.
+    pub txid: Option<String>,
+    /// The virtual size of the finalized transaction, witnesses included.
+    pub vsize: Option<usize>,
+    /// Why nothing could be extracted, with the code `NOT_FINALIZED`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<Warning>,
+}
+
+/// A partial signature that doesn't verify for its input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InvalidSignature {
+    pub input: usize,
+    pub pubkey: String,
+}
+
+/// The core figures of a summary, returned in the minimal detail mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MinimalSummary {
+    pub txid: String,
+    #[serde(
+        serialize_with = "serialize_optional_sat",
+        deserialize_with = "deserialize_optional_sat"
+    )]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<u64>"))]
+    pub fee: Option<Amount>,
+    pub fee_rate_sat_vb: Option<f64>,
+    #[serde(serialize_with = "serialize_sat", deserialize_with = "deserialize_sat")]
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub total_output_amount: Amount,
+    pub output_count: usize,
+}
+
+impl From<&PsbtSummary> for MinimalSummary {
+    fn from(summary: &PsbtSummary) -> Self {
+        MinimalSummary {
+            txid: summary.txid.clone(),
+            fee: summary.fee,
+            fee_rate_sat_vb: summary.fee_rate_sat_vb,
+            total_output_amount: summary.total_output_amount,
+            output_count: summary.output_count,
+        }
+    }
+}
+
+/// The sighash a signer should have signed for an input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DebugSighash {
+    pub input: usize,
+    /// `legacy` or `bip143`.
+    pub algorithm: Option<String>,
+    pub sighash_type: String,
+    /// The 32 byte message signed, as hex, unknown when the input's UTXO or
+    /// a script it needs is missing.
+    pub sighash: Option<String>,
+}
+
+/// An `m`-of-`n` multisig threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Multisig {
+    pub m: usize,
+    pub n: usize,
+}
+
+/// An output of the transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct OutputDetail {
+    /// The position of the output in the transaction, its vout.
+    pub index: usize,
+    #[serde(serialize_with = "serialize_sat", deserialize_with = "deserialize_sat")]
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub amount: Amount,
+    /// The amount in BTC, with 8 fractional digits.
+    pub amount_btc: String,
+    pub pay_to: Option<String>,
+    /// `base58`, `bech32` or `bech32m`, the encoding of the address.
+    pub address_encoding: Option<String>,
+    pub script_type: String,
+    /// Whether the output pays back to the signer's wallet.
+    pub is_change: bool,
+    /// Whether the amount is below the dust limit for the script type.
+    pub is_dust: bool,
+    /// Whether the output can ever be spent, false for `OP_RETURN` data.
+    pub spendable: bool,
+    /// The BIP32 origins of the keys the output pays to.
+    pub derivations: Vec<Derivation>,
+    /// The data embedded in an `OP_RETURN` output, as hex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// The threshold of a bare multisig output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multisig: Option<Multisig>,
+    /// The script of a nonstandard output, as hex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script_hex: Option<String>,
+}
+
+/// An extended public key from the PSBT's global map, with its origin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GlobalXpub {
+    pub xpub: String,
+    /// The master key fingerprint as 8 hex characters.
+    pub master_fingerprint: String,
+    /// The derivation path in `m/...` notation.
+    pub derivation_path: String,
+}
+
+/// The key-value pairs of the PSBT that `bitcoin` doesn't interpret, by map.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct UnknownFields {
+    pub global: Vec<UnknownField>,
+    /// The fields of each input map, in input order.
+    pub inputs: Vec<Vec<UnknownField>>,
+    /// The fields of each output map, in output order.
+    pub outputs: Vec<Vec<UnknownField>>,
+}
+
+/// An unknown or proprietary PSBT key-value pair.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct UnknownField {
+    /// The key type followed by the key data, as hex.
+    pub key: String,
+    pub value: String,
+}
+
+/// How a PSBT returned by a signer differs from the one it was sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DiffResult {
+    /// Whether both PSBTs carry the same unsigned transaction.
+    pub unsigned_tx_identical: bool,
+    /// The inputs that gained partial signatures.
+    pub signed_inputs: Vec<usize>,
+    /// The changes to the transaction, each with the code `TAMPERED`.
+    pub tampered: Vec<Warning>,
+}
+
+/// The BIP32 origin of a public key, formatted like the global xpubs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Derivation {
+    pub pubkey: String,
+    /// The master key fingerprint as 8 hex characters.
+    pub master_fingerprint: String,
+    /// The derivation path in `m/...` notation.
+    pub derivation_path: String,
+}
+
+/// A non-fatal problem found while validating a PSBT.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Warning {
+    pub code: Cow<'static, str>,
+    pub message: String,
+}
+
+impl Warning {
+    pub(crate) fn new(code: &'static str, message: String) -> Self {
+        Warning {
+            code: Cow::Borrowed(code),
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{p2wpkh, psbt, txout};
+    use crate::tests::summarize;
+    use crate::ParseOptions;
+
+    #[test]
+    fn summary_round_trips_through_json() {
+        let psbt = psbt(
+            &[txout(100_000, p2wpkh(1))],
+            &[txout(60_000, p2wpkh(2)), txout(39_000, p2wpkh(3))],
+        );
+        let options = ParseOptions {
+            validate: true,
+            explain: true,
+            emit_bip21: true,
+            ..Default::default()
+        };
+        let summary = summarize(&psbt, &options);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["fee"], 1_000);
+        assert_eq!(json["pay_to_info"][0]["amount"], 60_000);
+
+        let parsed: PsbtSummary = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(parsed.fee, Some(Amount::from_sat(1_000)));
+        assert_eq!(parsed.network, summary.network);
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+    }
+
+    #[test]
+    fn payment_uri_is_only_rendered_on_request() {
+        let psbt = psbt(
+            &[txout(1_100_000, p2wpkh(1))],
+            &[txout(1_000_000, p2wpkh(2)), txout(90_000, p2wpkh(3))],
+        );
+        let summary = summarize(&psbt, &ParseOptions::default());
+        let json = serde_json::to_value(&summary).unwrap();
+        assert!(json.get("payment_uri").is_none());
+
+        let options = ParseOptions {
+            emit_bip21: true,
+            ..Default::default()
+        };
+        let summary = summarize(&psbt, &options);
+        let address = summary.pay_to_info[0].pay_to.clone().unwrap();
+        assert_eq!(
+            summary.payment_uri.unwrap(),
+            format!("bitcoin:{}?amount=0.01", address)
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use bitcoin::blockdata::transaction::TxOut;
+use bitcoin::hashes::hex::ToHex;
+use bitcoin::util::psbt::Input;
+
+use crate::script::is_v1_p2tr;
+use crate::TaprootSpend;
+
+/// Key types of the BIP371 input fields, which this version of `bitcoin`
+/// keeps among the unknown fields.
+const PSBT_IN_TAP_KEY_SIG: u8 = 0x13;
+const PSBT_IN_TAP_SCRIPT_SIG: u8 = 0x14;
+const PSBT_IN_TAP_LEAF_SCRIPT: u8 = 0x15;
+const PSBT_IN_TAP_INTERNAL_KEY: u8 = 0x17;
+const PSBT_IN_TAP_MERKLE_ROOT: u8 = 0x18;
+
+/// The value of the first unknown field of `input` with key type `type_value`.
+fn tap_field(input: &Input, type_value: u8) -> Option<&[u8]> {
+    input
+        .unknown
+        .iter()
+        .find(|(key, _)| key.type_value == type_value)
+        .map(|(_, value)| value.as_slice())
+}
+
+/// Whether `input` carries a Taproot key path signature.
+pub(crate) fn has_tap_key_sig(input: &Input) -> bool {
+    tap_field(input, PSBT_IN_TAP_KEY_SIG).is_some()
+}
+
+/// Describes how an input spending a Taproot output is, or will be, spent.
+///
+/// A final witness of a single element is a key path spend, a longer one
+/// reveals a script. Before finalizing, a key signature marks a key path
+/// spend while script signatures or leaf scripts mark a script path one.
+pub(crate) fn taproot_spend(input: &Input, utxo: Option<&TxOut>) -> Option<TaprootSpend> {
+    let has_tap_fields = input
+        .unknown
+        .keys()
+        .any(|key| (PSBT_IN_TAP_KEY_SIG..=PSBT_IN_TAP_MERKLE_ROOT).contains(&key.type_value));
+    if !has_tap_fields && !utxo.is_some_and(|utxo| is_v1_p2tr(&utxo.script_pubkey)) {
+        return None;
+    }
+
+    let spend_path = match &input.final_script_witness {
+        Some(witness) if witness.len() == 1 => Some("key"),
+        Some(_) => Some("script"),
+        None if has_tap_key_sig(input) => Some("key"),
+        None if tap_field(input, PSBT_IN_TAP_SCRIPT_SIG).is_some()
+            || tap_field(input, PSBT_IN_TAP_LEAF_SCRIPT).is_some() =>
+        {
+            Some("script")
+        }
+        None => None,
+    };
+    Some(TaprootSpend {
+        spend_path: spend_path.map(str::to_owned),
+        internal_key: tap_field(input, PSBT_IN_TAP_INTERNAL_KEY).map(|key| key.to_hex()),
+        merkle_root: tap_field(input, PSBT_IN_TAP_MERKLE_ROOT).map(|root| root.to_hex()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::blockdata::script::Builder;
+    use bitcoin::util::psbt::raw::Key;
+
+    use super::*;
+    use crate::test_util::{p2wpkh, psbt, txout};
+    use crate::tests::summarize;
+    use crate::ParseOptions;
+
+    /// Sets the BIP371 field `type_value` of `input` to `value`.
+    fn set_tap_field(input: &mut Input, type_value: u8, value: &[u8]) {
+        let key = Key {
+            type_value,
+            key: Vec::new(),
+        };
+        input.unknown.insert(key, value.to_vec());
+    }
+
+    #[test]
+    fn reads_the_tap_fields_of_a_key_path_spend() {
+        let p2tr = Builder::new()
+            .push_int(1)
+            .push_slice(&[7; 32])
+            .into_script();
+        let mut psbt = psbt(&[txout(10_000, p2tr)], &[txout(9_000, p2wpkh(1))]);
+        set_tap_field(&mut psbt.inputs[0], PSBT_IN_TAP_INTERNAL_KEY, &[2; 32]);
+        set_tap_field(&mut psbt.inputs[0], PSBT_IN_TAP_MERKLE_ROOT, &[3; 32]);
+
+        let unsigned = summarize(&psbt, &ParseOptions::default());
+        let taproot = unsigned.inputs[0].taproot.as_ref().unwrap();
+        assert_eq!(taproot.spend_path, None);
+        assert_eq!(taproot.internal_key, Some("02".repeat(32)));
+        assert_eq!(taproot.merkle_root, Some("03".repeat(32)));
+        assert_eq!(unsigned.signing_status, "unsigned");
+
+        set_tap_field(&mut psbt.inputs[0], PSBT_IN_TAP_KEY_SIG, &[4; 64]);
+        let signed = summarize(&psbt, &ParseOptions::default());
+        let taproot = signed.inputs[0].taproot.as_ref().unwrap();
+        assert_eq!(taproot.spend_path.as_deref(), Some("key"));
+        assert_eq!(signed.signing_status, "complete");
+    }
+}
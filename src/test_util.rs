@@ -0,0 +1,146 @@
+//! PSBT fixtures shared by the unit tests and, through `tests/common`, the
+//! integration tests, so it only depends on `bitcoin`.
+
+use bitcoin::blockdata::opcodes::all::{OP_CHECKMULTISIG, OP_RETURN};
+use bitcoin::blockdata::script::Builder;
+use bitcoin::blockdata::transaction::{OutPoint, SigHashType, Transaction, TxIn, TxOut};
+use bitcoin::consensus::encode::serialize;
+use bitcoin::hashes::Hash;
+use bitcoin::secp256k1::{Message, Secp256k1, SecretKey};
+use bitcoin::util::bip143::SigHashCache;
+use bitcoin::util::bip32::{Fingerprint, KeySource};
+use bitcoin::util::psbt::PartiallySignedTransaction;
+use bitcoin::{Network, PrivateKey, PublicKey, Script, Txid};
+
+/// A DER signature with a `SIGHASH_ALL` byte, which only verifies when
+/// signature verification isn't asked for.
+pub(crate) const DUMMY_SIG: [u8; 9] = [0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01, 0x01];
+
+/// The secret key made of `seed` bytes, which mustn't be zero.
+pub(crate) fn secret_key(seed: u8) -> SecretKey {
+    SecretKey::from_slice(&[seed; 32]).unwrap()
+}
+
+/// The compressed public key of `secret_key(seed)`.
+pub(crate) fn pubkey(seed: u8) -> PublicKey {
+    let key = PrivateKey {
+        compressed: true,
+        network: Network::Bitcoin,
+        key: secret_key(seed),
+    };
+    PublicKey::from_private_key(&Secp256k1::signing_only(), &key)
+}
+
+/// A P2WPKH script paying to `pubkey(seed)`.
+pub(crate) fn p2wpkh(seed: u8) -> Script {
+    Script::new_v0_wpkh(&pubkey(seed).wpubkey_hash().unwrap())
+}
+
+/// A P2PKH script paying to `pubkey(seed)`.
+pub(crate) fn p2pkh(seed: u8) -> Script {
+    Script::new_p2pkh(&pubkey(seed).pubkey_hash())
+}
+
+/// An `OP_RETURN` script embedding `data`.
+pub(crate) fn op_return(data: &[u8]) -> Script {
+    Builder::new()
+        .push_opcode(OP_RETURN)
+        .push_slice(data)
+        .into_script()
+}
+
+/// A BIP32 key source under the master fingerprint made of `fingerprint`
+/// bytes, at `path` such as `m/84'/0'/0'/0/0`.
+pub(crate) fn key_source(fingerprint: u8, path: &str) -> KeySource {
+    (
+        Fingerprint::from(&[fingerprint; 4][..]),
+        path.parse().unwrap(),
+    )
+}
+
+/// Adds the `SIGHASH_ALL` signature of `secret_key(seed)` to input `index`,
+/// which spends a witness UTXO paying to P2WPKH, nested or not, or to the
+/// input's witness script, or else a legacy non-witness UTXO.
+pub(crate) fn sign(psbt: &mut PartiallySignedTransaction, index: usize, seed: u8) {
+    let tx = &psbt.global.unsigned_tx;
+    let input = &psbt.inputs[index];
+    let sighash = match &input.witness_utxo {
+        Some(utxo) => {
+            let script_code = input.witness_script.clone().unwrap_or_else(|| p2pkh(seed));
+            SigHashCache::new(tx)
+                .signature_hash(index, &script_code, utxo.value, SigHashType::All)
+                .into_inner()
+        }
+        None => {
+            let vout = tx.input[index].previous_output.vout as usize;
+            let utxo = &input.non_witness_utxo.as_ref().unwrap().output[vout];
+            tx.signature_hash(index, &utxo.script_pubkey, SigHashType::All.as_u32())
+                .into_inner()
+        }
+    };
+    let message = Message::from_slice(&sighash).unwrap();
+    let mut signature = Secp256k1::signing_only()
+        .sign(&message, &secret_key(seed))
+        .serialize_der()
+        .to_vec();
+    signature.push(SigHashType::All.as_u32() as u8);
+    psbt.inputs[index]
+        .partial_sigs
+        .insert(pubkey(seed), signature);
+}
+
+/// An `m`-of-`n` multisig script over the keys of seeds 1 through `n`.
+pub(crate) fn multisig(m: u8, n: u8) -> Script {
+    let mut builder = Builder::new().push_int(m.into());
+    for seed in 1..=n {
+        builder = builder.push_key(&pubkey(seed));
+    }
+    builder
+        .push_int(n.into())
+        .push_opcode(OP_CHECKMULTISIG)
+        .into_script()
+}
+
+/// A version 2 transaction spending `inputs` distinct outpoints and
+/// paying `outputs`.
+pub(crate) fn unsigned_tx(inputs: usize, outputs: &[TxOut]) -> Transaction {
+    Transaction {
+        version: 2,
+        lock_time: 0,
+        input: (0..inputs)
+            .map(|index| TxIn {
+                previous_output: OutPoint {
+                    txid: Txid::hash(&(index as u64).to_le_bytes()),
+                    vout: 0,
+                },
+                script_sig: Script::new(),
+                sequence: 0xffff_ffff,
+                witness: Vec::new(),
+            })
+            .collect(),
+        output: outputs.to_vec(),
+    }
+}
+
+/// A PSBT whose inputs spend the segwit `utxos`, given as witness UTXOs.
+pub(crate) fn psbt(utxos: &[TxOut], outputs: &[TxOut]) -> PartiallySignedTransaction {
+    let mut psbt =
+        PartiallySignedTransaction::from_unsigned_tx(unsigned_tx(utxos.len(), outputs)).unwrap();
+    for (input, utxo) in psbt.inputs.iter_mut().zip(utxos) {
+        input.witness_utxo = Some(utxo.clone());
+    }
+    psbt
+}
+
+/// An output paying `value` to `script_pubkey`.
+pub(crate) fn txout(value: u64, script_pubkey: Script) -> TxOut {
+    TxOut {
+        value,
+        script_pubkey,
+    }
+}
+
+/// The base64 encoding of `psbt`.
+pub(crate) fn base64(psbt: &PartiallySignedTransaction) -> String {
+    base64::encode(serialize(psbt))
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bitcoin::blockdata::transaction::SigHashType;
+use bitcoin::util::psbt::PartiallySignedTransaction;
+use bitcoin::Network;
+
+use crate::{coin_type_network, ParseOptions, PsbtSummary, Warning};
+
+/// The fee rate, in sat/vB, above which a PSBT is flagged by default.
+pub const DEFAULT_HIGH_FEE_RATE: f64 = 1000.0;
+
+/// The nSequence value that opts an input out of locktime enforcement.
+const FINAL_SEQUENCE: u32 = 0xffff_ffff;
+
+/// The share of the input value above which a fee is flagged.
+const HIGH_FEE_PERCENT: u64 = 10;
+
+/// Runs the opt-in sanity checks over a parsed summary of `psbt`.
+pub(crate) fn validate(
+    summary: &PsbtSummary,
+    psbt: &PartiallySignedTransaction,
+    options: &ParseOptions,
+) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let high_fee_rate = options.high_fee_rate.unwrap_or(DEFAULT_HIGH_FEE_RATE);
+
+    if let Some(fee_rate) = summary.fee_rate_sat_vb.filter(|rate| *rate > high_fee_rate) {
+        warnings.push(Warning::new(
+            "HIGH_FEE_RATE",
+            format!(
+                "fee rate of {} sat/vB exceeds {} sat/vB",
+                fee_rate, high_fee_rate
+            ),
+        ));
+    }
+
+    if let Some((fee, input_amount)) =
+        summary
+            .fee
+            .zip(summary.total_input_amount)
+            .filter(|(fee, input_amount)| {
+                u128::from(fee.as_sat()) * 100
+                    > u128::from(input_amount.as_sat()) * u128::from(HIGH_FEE_PERCENT)
+            })
+    {
+        warnings.push(Warning::new(
+            "FEE_EXCEEDS_10PCT",
+            format!(
+                "fee of {} sat is more than {}% of the {} sat being spent",
+                fee.as_sat(),
+                HIGH_FEE_PERCENT,
+                input_amount.as_sat()
+            ),
+        ));
+    }
+
+    warnings.extend(input_amount_mismatches(summary, options));
+
+    // Inputs and outputs sharing an address link them to the same owner
+    let mut uses: BTreeMap<&str, usize> = BTreeMap::new();
+    let input_addresses = summary.inputs.iter().map(|input| &input.address);
+    let output_addresses = summary.pay_to_info.iter().map(|output| &output.pay_to);
+    for address in input_addresses.chain(output_addresses).flatten() {
+        *uses.entry(address).or_default() += 1;
+    }
+    let reused: Vec<&str> = uses
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .map(|(address, _)| address)
+        .collect();
+    if !reused.is_empty() {
+        warnings.push(Warning::new(
+            "ADDRESS_REUSE",
+            format!(
+                "addresses used more than once in the transaction: {}",
+                reused.join(", ")
+            ),
+        ));
+    }
+
+    // Locktimes only bind once an input opts in with a non-final sequence.
+    // Heights can't be judged without the chain, but timestamps can
+    let enforced = summary
+        .inputs
+        .iter()
+        .any(|input| input.sequence != FINAL_SEQUENCE);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    if enforced && summary.locktime_type == "timestamp" && u64::from(summary.locktime) > now {
+        warnings.push(Warning::new(
+            "FUTURE_LOCKTIME",
+            format!(
+                "locktime {} is in the future, so the transaction can't be mined yet",
+                summary.locktime
+            ),
+        ));
+    }
+
+    // SIGHASH_SINGLE without a matching output leaves the outputs unsigned:
+    // legacy inputs sign the constant 1 and segwit ones no outputs at all
+    for (index, input) in psbt.inputs.iter().enumerate() {
+        let declared = matches!(
+            input.sighash_type,
+            Some(SigHashType::Single | SigHashType::SinglePlusAnyoneCanPay)
+        );
+        let signed = input
+            .partial_sigs
+            .values()
+            .any(|signature| signature.last().is_some_and(|byte| byte & 0x1f == 0x03));
+        if (declared || signed) && index >= summary.output_count {
+            warnings.push(Warning::new(
+                "SIGHASH_SINGLE_BUG",
+                format!(
+                    "input {} uses SIGHASH_SINGLE but there is no output {}, so its signature doesn't commit to any output",
+                    index, index
+                ),
+            ));
+        }
+    }
+
+    // Scripts render as an address on any network, so a foreign output only
+    // shows in the coin type its key was derived with
+    let mainnet = summary.network == Network::Bitcoin;
+    for (index, output) in psbt.outputs.iter().enumerate() {
+        let foreign = output
+            .bip32_derivation
+            .values()
+            .filter_map(|(_, path)| coin_type_network(path))
+            .find(|network| (*network == Network::Bitcoin) != mainnet);
+        if let Some(network) = foreign {
+            warnings.push(Warning::new(
+                "WRONG_NETWORK_OUTPUT",
+                format!(
+                    "output {} pays to a key derived for {}, not {}",
+                    index, network, summary.network
+                ),
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// Flags the inputs whose amount in `options.input_amounts` was ignored for
+/// the one in the PSBT's UTXO data.
+pub(crate) fn input_amount_mismatches(
+    summary: &PsbtSummary,
+    options: &ParseOptions,
+) -> Vec<Warning> {
+    let mut mismatches = Vec::new();
+    let overrides = options.input_amounts.iter().flatten();
+    for (input, given) in summary.inputs.iter().zip(overrides) {
+        if let (Some(amount), Some(given)) = (input.amount, *given) {
+            if amount != given {
+                mismatches.push(Warning::new(
+                    "INPUT_AMOUNT_MISMATCH",
+                    format!(
+                        "input {} spends {} sat according to the PSBT, not the {} sat given",
+                        input.index,
+                        amount.as_sat(),
+                        given.as_sat()
+                    ),
+                ));
+            }
+        }
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::blockdata::transaction::SigHashType;
+    use bitcoin::util::psbt::PartiallySignedTransaction;
+
+    use super::FINAL_SEQUENCE;
+    use crate::test_util::{key_source, p2pkh, p2wpkh, psbt, pubkey, txout};
+    use crate::tests::summarize;
+    use crate::ParseOptions;
+
+    /// The codes of the warnings validating `psbt` raises.
+    fn warning_codes(psbt: &PartiallySignedTransaction) -> Vec<String> {
+        let options = ParseOptions {
+            validate: true,
+            ..Default::default()
+        };
+        summarize(psbt, &options)
+            .warnings
+            .unwrap()
+            .into_iter()
+            .map(|warning| warning.code.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn flags_only_a_high_fee() {
+        let normal = psbt(
+            &[txout(100_000, p2wpkh(1))],
+            &[txout(60_000, p2pkh(2)), txout(38_560, p2wpkh(3))],
+        );
+        assert!(warning_codes(&normal).is_empty());
+
+        let high = psbt(&[txout(1_000_000, p2wpkh(1))], &[txout(10_000, p2wpkh(2))]);
+        assert_eq!(warning_codes(&high), ["HIGH_FEE_RATE", "FEE_EXCEEDS_10PCT"]);
+    }
+
+    #[test]
+    fn flags_an_address_paid_back_to() {
+        let psbt = psbt(
+            &[txout(100_000, p2wpkh(1))],
+            &[txout(60_000, p2pkh(2)), txout(39_000, p2wpkh(1))],
+        );
+        assert_eq!(warning_codes(&psbt), ["ADDRESS_REUSE"]);
+    }
+
+    #[test]
+    fn flags_an_output_derived_for_another_network() {
+        let mut psbt = psbt(
+            &[txout(100_000, p2wpkh(1))],
+            &[txout(60_000, p2pkh(2)), txout(39_000, p2wpkh(3))],
+        );
+        psbt.inputs[0]
+            .bip32_derivation
+            .insert(pubkey(1), key_source(1, "m/84'/0'/0'/0/0"));
+        psbt.outputs[1]
+            .bip32_derivation
+            .insert(pubkey(3), key_source(1, "m/84'/1'/0'/1/0"));
+
+        let options = ParseOptions {
+            validate: true,
+            ..Default::default()
+        };
+        let warnings = summarize(&psbt, &options).warnings.unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "WRONG_NETWORK_OUTPUT");
+        assert_eq!(
+            warnings[0].message,
+            "output 1 pays to a key derived for testnet, not bitcoin"
+        );
+    }
+
+    #[test]
+    fn flags_an_enforced_future_timestamp() {
+        let mut psbt = psbt(&[txout(10_000, p2wpkh(1))], &[txout(9_000, p2wpkh(2))]);
+        // 2100-01-01, beyond any clock this runs on
+        psbt.global.unsigned_tx.lock_time = 4_102_444_800;
+        assert!(warning_codes(&psbt).is_empty());
+
+        psbt.global.unsigned_tx.input[0].sequence = FINAL_SEQUENCE - 1;
+        assert_eq!(warning_codes(&psbt), ["FUTURE_LOCKTIME"]);
+    }
+
+    #[test]
+    fn flags_sighash_single_without_a_matching_output() {
+        let mut psbt = psbt(
+            &[txout(10_000, p2wpkh(1)), txout(10_000, p2wpkh(2))],
+            &[txout(19_000, p2wpkh(3))],
+        );
+        psbt.inputs[0].sighash_type = Some(SigHashType::Single);
+        assert!(warning_codes(&psbt).is_empty());
+
+        psbt.inputs[1].sighash_type = Some(SigHashType::Single);
+        assert_eq!(warning_codes(&psbt), ["SIGHASH_SINGLE_BUG"]);
+    }
+}
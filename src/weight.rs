@@ -0,0 +1,204 @@
+use bitcoin::blockdata::script::Script;
+use bitcoin::util::psbt::{Input, PartiallySignedTransaction};
+use bitcoin::TxOut;
+
+use crate::resolve_utxo;
+use crate::script::{is_v1_p2tr, multisig_threshold};
+
+// Upper bounds for a DER signature with its sighash byte, a compressed public
+// key and a Schnorr signature with the default sighash
+const ECDSA_SIG_SIZE: usize = 72;
+const PUBKEY_SIZE: usize = 33;
+const SCHNORR_SIG_SIZE: usize = 64;
+
+/// The sizes of the scriptSig and witness an input would be finalized with.
+struct InputWeight {
+    script_sig: usize,
+    witness: Option<usize>,
+}
+
+/// Size of the compact size prefix encoding `n`.
+fn varint_size(n: usize) -> usize {
+    match n {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x10000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+/// Size of the opcode pushing `len` bytes of data.
+fn push_size(len: usize) -> usize {
+    match len {
+        0..=75 => 1,
+        76..=0xff => 2,
+        0x100..=0xffff => 3,
+        _ => 5,
+    }
+}
+
+/// Size of a witness stack with items of the given sizes.
+fn witness_size(items: &[usize]) -> usize {
+    varint_size(items.len())
+        + items
+            .iter()
+            .map(|len| varint_size(*len) + len)
+            .sum::<usize>()
+}
+
+/// Size of the items satisfying a P2WPKH or P2PKH output: a signature and a key.
+fn pubkey_hash_items() -> [usize; 2] {
+    [ECDSA_SIG_SIZE, PUBKEY_SIZE]
+}
+
+/// Sizes of the items satisfying a multisig `script`: the dummy element of
+/// `OP_CHECKMULTISIG`, `m` signatures and the script itself.
+fn multisig_items(script: &Script) -> Option<Vec<usize>> {
+    let multisig = multisig_threshold(script)?;
+    let mut items = vec![0];
+    items.extend(std::iter::repeat_n(ECDSA_SIG_SIZE, multisig.m));
+    items.push(script.len());
+    Some(items)
+}
+
+/// Size of a scriptSig pushing each of `items`.
+fn script_sig_size(items: &[usize]) -> usize {
+    items
+        .iter()
+        .map(|len| if *len == 0 { 1 } else { push_size(*len) + len })
+        .sum()
+}
+
+/// Estimates what finalizing `input` adds to the transaction, or `None` when
+/// its script can't be satisfied in a known way.
+fn estimate_input(input: &Input, utxo: Option<&TxOut>) -> Option<InputWeight> {
+    // A finalized input's scriptSig and witness are known exactly
+    if input.final_script_sig.is_some() || input.final_script_witness.is_some() {
+        return Some(InputWeight {
+            script_sig: input
+                .final_script_sig
+                .as_ref()
+                .map_or(0, |script| script.len()),
+            witness: input.final_script_witness.as_ref().map(|witness| {
+                witness_size(&witness.iter().map(|item| item.len()).collect::<Vec<_>>())
+            }),
+        });
+    }
+
+    let script_pubkey = &utxo?.script_pubkey;
+    let (script_pubkey, script_sig) = if script_pubkey.is_p2sh() {
+        let redeem_script = input.redeem_script.as_ref()?;
+        if !redeem_script.is_witness_program() {
+            let items = multisig_items(redeem_script)?;
+            return Some(InputWeight {
+                script_sig: script_sig_size(&items),
+                witness: None,
+            });
+        }
+        // Nested segwit pushes the witness program in the scriptSig
+        (redeem_script, script_sig_size(&[redeem_script.len()]))
+    } else {
+        (script_pubkey, 0)
+    };
+
+    let weight = if script_pubkey.is_p2pkh() {
+        InputWeight {
+            script_sig: script_sig_size(&pubkey_hash_items()),
+            witness: None,
+        }
+    } else if script_pubkey.is_v0_p2wpkh() {
+        InputWeight {
+            script_sig,
+            witness: Some(witness_size(&pubkey_hash_items())),
+        }
+    } else if script_pubkey.is_v0_p2wsh() {
+        let items = multisig_items(input.witness_script.as_ref()?)?;
+        InputWeight {
+            script_sig,
+            witness: Some(witness_size(&items)),
+        }
+    } else if is_v1_p2tr(script_pubkey) {
+        // Assumes a key path spend
+        InputWeight {
+            script_sig,
+            witness: Some(witness_size(&[SCHNORR_SIG_SIZE])),
+        }
+    } else {
+        return None;
+    };
+    Some(weight)
+}
+
+/// Estimates the weight of the transaction once every input is finalized,
+/// starting from the weight of the unsigned transaction, which carries empty
+/// scriptSigs and no witnesses.
+///
+/// Signatures are counted at their maximum size and taproot inputs are
+/// assumed to be spent by key path. Returns `None` when an input's UTXO is
+/// unknown or its script isn't one of the standard single-key or multisig
+/// templates.
+pub(crate) fn estimate_final_weight(
+    unsigned_weight: usize,
+    inputs: &[Input],
+    utxos: &[Option<&TxOut>],
+) -> Option<usize> {
+    let estimates: Vec<InputWeight> = inputs
+        .iter()
+        .zip(utxos)
+        .map(|(input, utxo)| estimate_input(input, *utxo))
+        .collect::<Option<_>>()?;
+
+    // The empty scriptSigs are already counted with their one byte prefix
+    let script_sig_weight: usize = estimates
+        .iter()
+        .map(|estimate| 4 * (estimate.script_sig + varint_size(estimate.script_sig) - 1))
+        .sum();
+
+    // Once any input has a witness, the marker and flag bytes are added and
+    // every input without one carries an empty stack
+    let witness_weight = if estimates.iter().any(|estimate| estimate.witness.is_some()) {
+        2 + estimates
+            .iter()
+            .map(|estimate| estimate.witness.unwrap_or(1))
+            .sum::<usize>()
+    } else {
+        0
+    };
+
+    Some(unsigned_weight + script_sig_weight + witness_weight)
+}
+
+/// Estimates the virtual size of `psbt`'s transaction once every input is
+/// finalized, see `estimate_final_weight`. Falls back to the size of the
+/// unsigned transaction when an input can't be estimated.
+pub fn estimate_final_vsize(psbt: &PartiallySignedTransaction) -> usize {
+    let tx = &psbt.global.unsigned_tx;
+    let utxos: Vec<Option<&TxOut>> = psbt
+        .inputs
+        .iter()
+        .zip(&tx.input)
+        .map(|(input, txin)| resolve_utxo(input, &txin.previous_output))
+        .collect();
+    let weight = tx.get_weight();
+    estimate_final_weight(weight, &psbt.inputs, &utxos)
+        .unwrap_or(weight)
+        .div_ceil(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{p2wpkh, psbt, txout};
+
+    #[test]
+    fn estimates_a_p2wpkh_spend() {
+        // The common one input, two P2WPKH output spend is 141 vB once
+        // signed, give or take a byte for the signature's length
+        let psbt = psbt(
+            &[txout(100_000, p2wpkh(1))],
+            &[txout(50_000, p2wpkh(2)), txout(49_000, p2wpkh(3))],
+        );
+        let vsize = estimate_final_vsize(&psbt);
+        assert!((140..=142).contains(&vsize), "{}", vsize);
+    }
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use std::process::Command;
+
+use lambda_http::Body;
+use psbt_parser::lambda::function_handler;
+
+#[tokio::test]
+async fn only_allowed_networks_are_parsed() {
+    // The list is read once, so it's set before the first request
+    std::env::set_var("ALLOWED_NETWORKS", "testnet, signet");
+    let request = |network: &str| {
+        let body = serde_json::json!({ "psbt": common::spend(1), "network": network });
+        lambda_http::http::Request::builder()
+            .method("POST")
+            .uri("/")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string().as_str()))
+            .unwrap()
+    };
+
+    let response = function_handler(request("testnet")).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = function_handler(request("bitcoin")).await.unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(common::json(&response)["error_code"], "NETWORK_NOT_ALLOWED");
+}
+
+#[test]
+fn unknown_allowed_network_stops_startup() {
+    let output = Command::new(env!("CARGO_BIN_EXE_psbt_parser"))
+        .env("ALLOWED_NETWORKS", "bitcoin,moonnet")
+        .env_remove("DEFAULT_NETWORK")
+        .env_remove("CORS_ALLOW_ORIGIN")
+        .env_remove("AWS_LAMBDA_RUNTIME_API")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("ALLOWED_NETWORKS") && stderr.contains("moonnet"));
+}
@@ -0,0 +1,33 @@
+use std::process::Command;
+
+const CLI: &str = env!("CARGO_BIN_EXE_psbt_parser_cli");
+
+/// The BIP174 test vector spending one non-witness and one witness UTXO.
+const PSBT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/bip174.psbt");
+
+#[test]
+fn summarizes_a_psbt_file() {
+    let output = Command::new(CLI)
+        .args(["--file", PSBT, "--network", "testnet"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        summary["txid"],
+        "fed6cd1fde4db4e13e7e800317e37f9cbd75ec364389670eeff80da993c7e560"
+    );
+    assert_eq!(summary["network"], "testnet");
+}
+
+#[test]
+fn fails_on_an_unknown_network() {
+    let output = Command::new(CLI)
+        .args(["--file", PSBT, "--network", "moonnet"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+}
@@ -0,0 +1,54 @@
+// Each test binary uses its own share of these helpers
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use lambda_http::http::header::CONTENT_TYPE;
+use lambda_http::{Body, Request, RequestExt, Response};
+
+#[path = "../../src/test_util.rs"]
+mod test_util;
+
+pub(crate) use test_util::*;
+
+/// A base64 PSBT spending `inputs` P2WPKH outputs of 10_000 sats each into
+/// one P2WPKH output, leaving 1_000 sats of fee.
+pub fn spend(inputs: usize) -> String {
+    base64(&psbt(
+        &vec![txout(10_000, p2wpkh(1)); inputs],
+        &[txout(10_000 * inputs as u64 - 1_000, p2wpkh(2))],
+    ))
+}
+
+/// A JSON POST request asking to parse `psbt`.
+pub fn post(psbt: &str) -> Request {
+    lambda_http::http::Request::builder()
+        .method("POST")
+        .uri("/")
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            serde_json::json!({ "psbt": psbt }).to_string().as_str(),
+        ))
+        .unwrap()
+}
+
+/// The JSON body of `response`.
+pub fn json(response: &Response<Body>) -> serde_json::Value {
+    serde_json::from_slice(response.body()).unwrap()
+}
+
+/// `request` with the query string API Gateway would have parsed out of it.
+pub fn with_query(request: Request, query: &[(&str, &str)]) -> Request {
+    let query: HashMap<String, String> = query
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    request.with_query_string_parameters(query)
+}
+
+/// Whether the body of `response` is indented JSON.
+pub fn is_pretty(response: &Response<Body>) -> bool {
+    std::str::from_utf8(response.body())
+        .unwrap()
+        .starts_with("{\n  ")
+}
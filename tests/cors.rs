@@ -0,0 +1,32 @@
+mod common;
+
+use std::process::Command;
+
+use lambda_http::http::header::ACCESS_CONTROL_ALLOW_ORIGIN;
+use psbt_parser::lambda::function_handler;
+
+#[tokio::test]
+async fn configured_origin_is_allowed() {
+    // The origin is read once, so it's set before the first request
+    std::env::set_var("CORS_ALLOW_ORIGIN", "https://wallet.example");
+
+    let response = function_handler(common::post(&common::spend(1)))
+        .await
+        .unwrap();
+    assert_eq!(
+        response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://wallet.example"
+    );
+}
+
+#[test]
+fn invalid_origin_stops_startup() {
+    let output = Command::new(env!("CARGO_BIN_EXE_psbt_parser"))
+        .env("CORS_ALLOW_ORIGIN", "https://wallet.example\n")
+        .env_remove("DEFAULT_NETWORK")
+        .env_remove("AWS_LAMBDA_RUNTIME_API")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("CORS_ALLOW_ORIGIN"));
+}
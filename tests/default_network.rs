@@ -0,0 +1,32 @@
+mod common;
+
+use std::process::Command;
+
+use bitcoin::Network;
+use psbt_parser::{parse_psbt, try_default_network};
+
+#[test]
+fn default_network_is_honoured() {
+    // The default is read once, so it's set before the first parse
+    std::env::set_var("DEFAULT_NETWORK", "Signet");
+    assert_eq!(try_default_network().unwrap(), Network::Signet);
+
+    let summary = parse_psbt(&common::spend(1), None).unwrap();
+    assert_eq!(summary.network, Network::Signet);
+    assert!(summary.inputs[0]
+        .address
+        .as_deref()
+        .unwrap()
+        .starts_with("tb1"));
+}
+
+#[test]
+fn unknown_default_network_stops_startup() {
+    let output = Command::new(env!("CARGO_BIN_EXE_psbt_parser"))
+        .env("DEFAULT_NETWORK", "moonnet")
+        .env_remove("AWS_LAMBDA_RUNTIME_API")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("moonnet"));
+}
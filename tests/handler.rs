@@ -0,0 +1,243 @@
+mod common;
+
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use lambda_http::http::header::{
+    ACCEPT_ENCODING, ACCESS_CONTROL_ALLOW_HEADERS, CONTENT_ENCODING, CONTENT_TYPE, VARY,
+};
+use lambda_http::{Body, Request};
+use psbt_parser::lambda::function_handler;
+
+#[tokio::test]
+async fn responses_vary_on_accept_encoding() {
+    let psbt = common::spend(1);
+
+    let response = function_handler(common::post(&psbt)).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    assert_eq!(response.headers()[VARY], "Accept-Encoding");
+
+    let mut request = common::post(&psbt);
+    request
+        .headers_mut()
+        .insert(ACCEPT_ENCODING, "gzip".parse().unwrap());
+    let response = function_handler(request).await.unwrap();
+    assert_eq!(response.headers()[CONTENT_ENCODING], "gzip");
+    assert_eq!(response.headers()[VARY], "Accept-Encoding");
+    let mut inflated = String::new();
+    GzDecoder::new(response.body().as_ref())
+        .read_to_string(&mut inflated)
+        .unwrap();
+    let plain = function_handler(common::post(&psbt)).await.unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&inflated).unwrap(),
+        common::json(&plain)
+    );
+
+    let mut request = common::post(&psbt);
+    request
+        .headers_mut()
+        .insert(ACCEPT_ENCODING, "gzip;q=0, identity".parse().unwrap());
+    let response = function_handler(request).await.unwrap();
+    assert!(response.headers().get(CONTENT_ENCODING).is_none());
+}
+
+#[tokio::test]
+async fn preflight_allows_gzipped_bodies() {
+    let request = lambda_http::http::Request::builder()
+        .method("OPTIONS")
+        .uri("/")
+        .body(lambda_http::Body::Empty)
+        .unwrap();
+    let response = function_handler(request).await.unwrap();
+    assert_eq!(response.status(), 204);
+    let allowed = response.headers()[ACCESS_CONTROL_ALLOW_HEADERS]
+        .to_str()
+        .unwrap();
+    assert!(allowed
+        .split(',')
+        .any(|header| header.trim().eq_ignore_ascii_case("Content-Encoding")));
+}
+
+#[tokio::test]
+async fn parses_for_each_requested_network() {
+    let request = lambda_http::http::Request::builder()
+        .method("POST")
+        .uri("/")
+        .header("Content-Type", "application/json")
+        .body(lambda_http::Body::from(
+            serde_json::json!({
+                "psbt": common::spend(1),
+                "networks": ["bitcoin", "testnet"],
+            })
+            .to_string()
+            .as_str(),
+        ))
+        .unwrap();
+    let response = function_handler(request).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let results = common::json(&response);
+    assert!(results["bitcoin"]["ok"]["pay_to_info"][0]["pay_to"]
+        .as_str()
+        .unwrap()
+        .starts_with("bc1"));
+    assert!(results["testnet"]["ok"]["pay_to_info"][0]["pay_to"]
+        .as_str()
+        .unwrap()
+        .starts_with("tb1"));
+}
+
+/// A POST request with `body`, sent as `content_type` when given.
+fn post_body(content_type: Option<&str>, body: Body) -> Request {
+    let mut builder = lambda_http::http::Request::builder()
+        .method("POST")
+        .uri("/");
+    if let Some(content_type) = content_type {
+        builder = builder.header(CONTENT_TYPE, content_type);
+    }
+    builder.body(body).unwrap()
+}
+
+#[tokio::test]
+async fn malformed_requests_are_refused() {
+    let cases = [
+        (Body::Empty, "EMPTY_BODY"),
+        (Body::from("psbt=cHNidP8B"), "INVALID_REQUEST"),
+        (Body::from(r#"{"psbt": "not a psbt!"}"#), "INVALID_BASE64"),
+    ];
+    for (body, error_code) in cases {
+        let response = function_handler(post_body(Some("application/json"), body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400);
+        assert_eq!(common::json(&response)["error_code"], error_code);
+    }
+}
+
+#[tokio::test]
+async fn parses_raw_psbt_bytes_for_the_queried_network() {
+    let bytes = base64::decode(common::spend(1)).unwrap();
+    let request = common::with_query(
+        post_body(Some("application/octet-stream"), Body::from(bytes)),
+        &[("network", "testnet")],
+    );
+    let response = function_handler(request).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let summary = common::json(&response);
+    assert_eq!(summary["network"], "testnet");
+    assert!(summary["pay_to_info"][0]["pay_to"]
+        .as_str()
+        .unwrap()
+        .starts_with("tb1"));
+}
+
+#[tokio::test]
+async fn get_reads_the_psbt_from_the_query_string() {
+    let psbt = common::spend(2);
+    assert!(psbt.contains('+'));
+    // API Gateway has already decoded the unescaped `+` to a space
+    let request = common::with_query(
+        get("/"),
+        &[("psbt", &psbt.replace('+', " ")), ("network", "testnet")],
+    );
+    let response = function_handler(request).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let summary = common::json(&response);
+    assert_eq!(summary["network"], "testnet");
+    assert_eq!(summary["input_count"], 2);
+}
+
+#[tokio::test]
+async fn gzipped_bodies_are_inflated() {
+    let json = serde_json::json!({ "psbt": common::spend(1) }).to_string();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(json.as_bytes()).unwrap();
+    let mut request = post_body(
+        Some("application/json"),
+        Body::from(encoder.finish().unwrap()),
+    );
+    request
+        .headers_mut()
+        .insert(CONTENT_ENCODING, "gzip".parse().unwrap());
+    let response = function_handler(request).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(common::json(&response)["input_count"], 1);
+
+    let mut request = post_body(Some("application/json"), Body::from(json.as_str()));
+    request
+        .headers_mut()
+        .insert(CONTENT_ENCODING, "gzip".parse().unwrap());
+    let response = function_handler(request).await.unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(common::json(&response)["error_code"], "INVALID_ENCODING");
+}
+
+#[tokio::test]
+async fn untyped_bodies_are_parsed_as_json_or_raw_psbt() {
+    let psbt = common::spend(1);
+    let json = serde_json::json!({ "psbt": psbt }).to_string();
+    let response = function_handler(post_body(None, Body::from(json.as_str())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let from_json = common::json(&response);
+
+    let bytes = base64::decode(&psbt).unwrap();
+    let response = function_handler(post_body(None, Body::from(bytes)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        common::json(&response)["summary_hash"],
+        from_json["summary_hash"]
+    );
+}
+
+/// A GET request for `path`.
+fn get(path: &str) -> Request {
+    lambda_http::http::Request::builder()
+        .method("GET")
+        .uri(path)
+        .body(Body::Empty)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn health_check_reports_ok() {
+    let response = function_handler(get("/healthz")).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        common::json(&response),
+        serde_json::json!({ "status": "ok" })
+    );
+}
+
+#[tokio::test]
+async fn schema_describes_each_response() {
+    let response = function_handler(get("/schema")).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let schema = common::json(&response);
+    assert_eq!(schema["summary"]["title"], "PsbtSummary");
+    assert_eq!(schema["minimal_summary"]["title"], "MinimalSummary");
+    assert_eq!(schema["error"]["title"], "ErrorResponse");
+    assert!(schema["summary"]["properties"]["fee"].is_object());
+}
+
+#[tokio::test]
+async fn errors_are_indented_when_asked() {
+    let request = common::with_query(
+        post_body(Some("application/json"), Body::Empty),
+        &[("pretty", "true")],
+    );
+    let response = function_handler(request).await.unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(common::json(&response)["error_code"], "EMPTY_BODY");
+    assert!(common::is_pretty(&response));
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use psbt_parser::lambda::function_handler;
+
+#[tokio::test]
+async fn request_past_the_limit_is_refused() {
+    // Settings are read once, so they're set before the first request
+    std::env::set_var("RATE_LIMIT_PER_MINUTE", "3");
+    let psbt = common::spend(1);
+    let request = |client: &str| {
+        let mut request = common::post(&psbt);
+        request
+            .headers_mut()
+            .insert("X-Forwarded-For", client.parse().unwrap());
+        request
+    };
+
+    for _ in 0..3 {
+        let response = function_handler(request("203.0.113.7")).await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+    let response = function_handler(request("203.0.113.7")).await.unwrap();
+    assert_eq!(response.status(), 429);
+    assert_eq!(common::json(&response)["error_code"], "RATE_LIMITED");
+    let pretty = common::with_query(request("203.0.113.7"), &[("pretty", "true")]);
+    let response = function_handler(pretty).await.unwrap();
+    assert_eq!(response.status(), 429);
+    assert!(common::is_pretty(&response));
+
+    // Other clients keep their own budget
+    let response = function_handler(request("203.0.113.8")).await.unwrap();
+    assert_eq!(response.status(), 200);
+}
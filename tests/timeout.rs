@@ -0,0 +1,21 @@
+mod common;
+
+use psbt_parser::lambda::function_handler;
+
+#[tokio::test]
+async fn slow_parse_times_out() {
+    // Settings are read once, so they're set before the first request
+    std::env::set_var("REQUEST_TIMEOUT_MS", "1");
+    std::env::set_var("MAX_PSBT_SIZE", "10000000");
+
+    let response = function_handler(common::post(&common::spend(5_000)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 504);
+    assert_eq!(common::json(&response)["error_code"], "TIMEOUT");
+
+    let pretty = common::with_query(common::post(&common::spend(5_000)), &[("pretty", "true")]);
+    let response = function_handler(pretty).await.unwrap();
+    assert_eq!(response.status(), 504);
+    assert!(common::is_pretty(&response));
+}